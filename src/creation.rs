@@ -0,0 +1,93 @@
+use alloc::vec::Vec;
+use alloy_primitives::Address;
+use revm::{
+    interpreter::{CallInputs, CallOutcome, CreateInputs, CreateOutcome},
+    Inspector,
+};
+
+/// An [Inspector] that tracks the nesting of contract creations, e.g. a factory that creates a
+/// contract whose constructor creates another contract.
+///
+/// Unlike the call depth, the creation depth only counts the active `CREATE` and `CREATE2`
+/// frames, regardless of the calls in between.
+#[derive(Clone, Debug, Default)]
+pub struct CreationInspector {
+    /// The active frames, each with the creator if it's a create frame and the number of
+    /// creation chains recorded when the frame started.
+    frames: Vec<(Option<Address>, usize)>,
+    /// All successful creations, see [CreationInspector::creation_chains].
+    chains: Vec<Vec<Address>>,
+}
+
+impl CreationInspector {
+    /// Returns the chain of creators of each successfully created contract, in the order the
+    /// creations completed.
+    ///
+    /// Each chain starts with the creator of the outermost active creation, followed by the
+    /// creator of each nested creation and ends with the created contract. A contract created by
+    /// a contract that is itself still being created therefore has a chain of length 3.
+    ///
+    /// Creations that were reverted, e.g. because an enclosing call failed, are not included.
+    pub fn creation_chains(&self) -> &[Vec<Address>] {
+        &self.chains
+    }
+
+    /// Returns the maximum creation depth, or `0` if no contract was created.
+    ///
+    /// A contract created by a regular call has a creation depth of 1, a contract created in
+    /// the constructor of that contract a depth of 2 and so on.
+    pub fn max_create_depth(&self) -> usize {
+        self.chains.iter().map(|chain| chain.len() - 1).max().unwrap_or_default()
+    }
+
+    /// Clears all recorded creations, so the inspector can be reused for the next transaction.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.chains.clear();
+    }
+
+    /// Starts tracking a new frame.
+    fn start_frame(&mut self, creator: Option<Address>) {
+        self.frames.push((creator, self.chains.len()));
+    }
+
+    /// Stops tracking the current frame, dropping all creations inside it if it failed.
+    fn end_frame(&mut self, success: bool, created: Option<Address>) {
+        let Some((creator, chains_len)) = self.frames.pop() else { return };
+        if !success {
+            self.chains.truncate(chains_len);
+            return;
+        }
+        if let (Some(creator), Some(created)) = (creator, created) {
+            let mut chain =
+                self.frames.iter().filter_map(|(creator, _)| *creator).collect::<Vec<_>>();
+            chain.extend([creator, created]);
+            self.chains.push(chain);
+        }
+    }
+}
+
+impl<CTX> Inspector<CTX> for CreationInspector {
+    fn call(&mut self, _context: &mut CTX, _inputs: &mut CallInputs) -> Option<CallOutcome> {
+        self.start_frame(None);
+        None
+    }
+
+    fn call_end(&mut self, _context: &mut CTX, _inputs: &CallInputs, outcome: &mut CallOutcome) {
+        self.end_frame(outcome.result.is_ok(), None);
+    }
+
+    fn create(&mut self, _context: &mut CTX, inputs: &mut CreateInputs) -> Option<CreateOutcome> {
+        self.start_frame(Some(inputs.caller));
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut CTX,
+        _inputs: &CreateInputs,
+        outcome: &mut CreateOutcome,
+    ) {
+        self.end_frame(outcome.result.is_ok(), outcome.address);
+    }
+}
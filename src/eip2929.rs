@@ -0,0 +1,217 @@
+use alloc::vec::Vec;
+use alloy_primitives::{map::HashSet, Address, TxKind, B256, U256};
+use revm::{
+    bytecode::opcode::{self, OpCode},
+    context::{transaction::AuthorizationTr, JournalTr},
+    context_interface::{transaction::AccessListItemTr, Block, Cfg, ContextTr, Transaction},
+    inspector::JournalExt,
+    interpreter::{
+        interpreter_types::{InputsTr, Jumps},
+        CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter,
+    },
+    primitives::hardfork::SpecId,
+    Inspector,
+};
+
+/// A single access of an account or storage slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccessRecord {
+    /// The opcode that accessed the account or storage slot.
+    pub opcode: OpCode,
+    /// The accessed account, or the account the storage slot belongs to.
+    pub address: Address,
+    /// The accessed storage slot, if this is a storage access.
+    pub slot: Option<U256>,
+    /// Whether this was the first access in the transaction, which is charged the cold cost.
+    pub cold: bool,
+    /// The index of the step in the transaction that performed the access.
+    pub step: usize,
+}
+
+/// An [Inspector] that records whether accounts and storage slots were cold or warm when they
+/// were accessed, as defined by [EIP-2929](https://eips.ethereum.org/EIPS/eip-2929).
+///
+/// The warm set mirrors the EVM's: it's initialized with the addresses that are warm at the
+/// start of the transaction, including the access list, and accesses made in reverted calls are
+/// cold again afterwards.
+#[derive(Clone, Debug, Default)]
+pub struct AccessRecordInspector {
+    /// All warm accounts
+    warm_accounts: HashSet<Address>,
+    /// All warm storage slots
+    warm_slots: HashSet<(Address, U256)>,
+    /// The accounts and storage slots warmed by each active call
+    warmed: Vec<Vec<(Address, Option<U256>)>>,
+    /// All recorded accesses
+    records: Vec<AccessRecord>,
+    /// The number of executed steps
+    step_count: usize,
+}
+
+impl AccessRecordInspector {
+    /// Returns all recorded accesses in execution order.
+    pub fn access_records(&self) -> &[AccessRecord] {
+        &self.records
+    }
+
+    /// Consumes the inspector and returns all recorded accesses in execution order.
+    pub fn into_access_records(self) -> Vec<AccessRecord> {
+        self.records
+    }
+
+    /// Clears all recorded accesses and warm accounts and slots, so the inspector can be reused
+    /// for the next transaction.
+    pub fn reset(&mut self) {
+        let Self { warm_accounts, warm_slots, warmed, records, step_count } = self;
+        warm_accounts.clear();
+        warm_slots.clear();
+        warmed.clear();
+        records.clear();
+        *step_count = 0;
+    }
+
+    /// Marks the account or storage slot as warm and returns true if it was cold.
+    fn warm(&mut self, address: Address, slot: Option<U256>) -> bool {
+        let was_cold = match slot {
+            Some(slot) => self.warm_slots.insert((address, slot)),
+            None => self.warm_accounts.insert(address),
+        };
+        if was_cold {
+            if let Some(warmed) = self.warmed.last_mut() {
+                warmed.push((address, slot));
+            }
+        }
+        was_cold
+    }
+
+    /// Warms all accounts and storage slots that are warm at the start of the transaction.
+    ///
+    /// Must be called before the top-level call.
+    fn warm_transaction<CTX: ContextTr<Journal: JournalExt>>(&mut self, context: &CTX) {
+        let tx = context.tx();
+        let from = tx.caller();
+        let to = if let TxKind::Call(to) = tx.kind() {
+            to
+        } else {
+            // The caller is loaded before the top-level call and its nonce is not increased yet.
+            let nonce = context
+                .journal_ref()
+                .evm_state()
+                .get(&from)
+                .map(|account| account.info.nonce)
+                .unwrap_or_default();
+            from.create(nonce)
+        };
+        self.warm_accounts.extend([from, to]);
+        self.warm_accounts.extend(context.journal_ref().precompile_addresses().iter().copied());
+        self.warm_accounts.extend(tx.authorization_list().flat_map(|auth| auth.authority()));
+
+        // EIP-3651: warm coinbase
+        if context.cfg().spec().into().is_enabled_in(SpecId::SHANGHAI) {
+            self.warm_accounts.insert(context.block().beneficiary());
+        }
+
+        for item in tx.access_list().into_iter().flatten() {
+            let address = *item.address();
+            self.warm_accounts.insert(address);
+            self.warm_slots.extend(item.storage_slots().map(|slot| (address, (*slot).into())));
+        }
+    }
+
+    /// Starts tracking the accounts and storage slots warmed by a new call.
+    fn start_call<CTX: ContextTr<Journal: JournalExt>>(&mut self, context: &CTX) {
+        if context.journal_ref().depth() == 0 {
+            self.warm_transaction(context);
+        }
+        self.warmed.push(Vec::new());
+    }
+
+    /// Stops tracking the current call, the accounts and storage slots it warmed become cold
+    /// again if the call failed.
+    fn end_call(&mut self, success: bool) {
+        let Some(warmed) = self.warmed.pop() else { return };
+        if success {
+            if let Some(parent) = self.warmed.last_mut() {
+                parent.extend(warmed);
+            }
+            return;
+        }
+        for (address, slot) in warmed {
+            match slot {
+                Some(slot) => self.warm_slots.remove(&(address, slot)),
+                None => self.warm_accounts.remove(&address),
+            };
+        }
+    }
+}
+
+impl<CTX> Inspector<CTX> for AccessRecordInspector
+where
+    CTX: ContextTr<Journal: JournalExt>,
+{
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        let step = self.step_count;
+        self.step_count += 1;
+
+        let op = interp.bytecode.opcode();
+        let (address, slot) = match op {
+            opcode::SLOAD | opcode::SSTORE => {
+                let Ok(slot) = interp.stack.peek(0) else { return };
+                (interp.input.target_address(), Some(slot))
+            }
+            opcode::BALANCE | opcode::EXTCODECOPY | opcode::EXTCODEHASH | opcode::EXTCODESIZE => {
+                let Ok(address) = interp.stack.peek(0) else { return };
+                (Address::from_word(B256::from(address.to_be_bytes())), None)
+            }
+            opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL => {
+                let Ok(address) = interp.stack.peek(1) else { return };
+                (Address::from_word(B256::from(address.to_be_bytes())), None)
+            }
+            _ => return,
+        };
+
+        let Some(opcode) = OpCode::new(op) else { return };
+        let cold = self.warm(address, slot);
+        self.records.push(AccessRecord { opcode, address, slot, cold, step });
+    }
+
+    fn call(&mut self, context: &mut CTX, _inputs: &mut CallInputs) -> Option<CallOutcome> {
+        self.start_call(context);
+        None
+    }
+
+    fn call_end(&mut self, _context: &mut CTX, _inputs: &CallInputs, outcome: &mut CallOutcome) {
+        self.end_call(outcome.result.is_ok());
+    }
+
+    fn create(&mut self, context: &mut CTX, inputs: &mut CreateInputs) -> Option<CreateOutcome> {
+        self.start_call(context);
+        if let Ok(account) = context.journal_mut().load_account(inputs.caller) {
+            let nonce = account.info.nonce;
+            self.warm(inputs.created_address(nonce), None);
+        }
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut CTX,
+        _inputs: &CreateInputs,
+        outcome: &mut CreateOutcome,
+    ) {
+        self.end_call(outcome.result.is_ok());
+    }
+
+    fn selfdestruct(&mut self, _contract: Address, target: Address, _value: U256) {
+        let cold = self.warm(target, None);
+        // the SELFDESTRUCT is the last executed step
+        let step = self.step_count.saturating_sub(1);
+        self.records.push(AccessRecord {
+            opcode: OpCode::SELFDESTRUCT,
+            address: target,
+            slot: None,
+            cold,
+            step,
+        });
+    }
+}
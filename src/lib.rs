@@ -4,6 +4,8 @@
 //!
 //! - `js-tracer`: Enables a JavaScript tracer implementation. This pulls in extra dependencies
 //!   (such as `boa`, `tokio` and `serde_json`).
+//! - `msgpack`: Enables the serialization of call traces to MessagePack (pulls in `rmp-serde`).
+//! - `profiling`: Records the time spent inside the callbacks of the tracing inspector.
 
 #![doc = include_str!("../README.md")]
 #![doc(
@@ -36,4 +38,22 @@ pub mod edge_cov;
 /// An inspector for tracking storage access.
 pub mod storage;
 
+/// An inspector for recording cold and warm accesses as defined by EIP-2929.
+pub mod eip2929;
+
+/// An inspector for tracking nested contract creations.
+pub mod creation;
+
+/// An inspector for recording copies of data into memory.
+pub mod memory_copy;
+
+/// An inspector for recording the gas paid for memory expansion.
+pub mod memory_expansion;
+
+/// An inspector for detecting reentrancy into a contract.
+pub mod reentrancy;
+
 pub use colorchoice::ColorChoice;
+
+/// An inspector that does nothing, e.g. as a baseline for the overhead of other inspectors.
+pub use revm::inspector::NoOpInspector;
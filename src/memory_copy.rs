@@ -0,0 +1,103 @@
+use alloc::vec::Vec;
+use alloy_primitives::{Address, B256, U256};
+use revm::{
+    bytecode::opcode::{self, OpCode},
+    interpreter::{
+        interpreter_types::{InputsTr, Jumps},
+        Interpreter,
+    },
+    Inspector,
+};
+
+/// A single copy of data into memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryCopy {
+    /// The opcode that copied the data: `MCOPY`, `CALLDATACOPY`, `CODECOPY`, `RETURNDATACOPY` or
+    /// `EXTCODECOPY`.
+    pub opcode: OpCode,
+    /// The address of the contract whose memory the data was copied into.
+    pub address: Address,
+    /// The account whose code was copied, only set for `EXTCODECOPY`.
+    pub code_address: Option<Address>,
+    /// The memory offset the data was copied to.
+    pub dest_offset: U256,
+    /// The offset of the data in its source: the memory, calldata, code or returndata.
+    pub src_offset: U256,
+    /// The number of copied bytes.
+    pub len: U256,
+}
+
+impl MemoryCopy {
+    /// Returns true if this is an `MCOPY` whose source and destination ranges overlap.
+    pub fn is_overlapping(&self) -> bool {
+        if self.opcode != OpCode::MCOPY || self.len.is_zero() {
+            return false;
+        }
+        let (lower, upper) = if self.dest_offset < self.src_offset {
+            (self.dest_offset, self.src_offset)
+        } else {
+            (self.src_offset, self.dest_offset)
+        };
+        upper - lower < self.len
+    }
+}
+
+/// An [Inspector] that records all copies of data into memory, with the offsets and lengths
+/// popped from the stack.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryCopyInspector {
+    /// All recorded copies
+    copies: Vec<MemoryCopy>,
+}
+
+impl MemoryCopyInspector {
+    /// Returns all recorded copies in execution order.
+    ///
+    /// See [Self::into_memory_copies] to take ownership of the copies without cloning them.
+    pub fn memory_copies(&self) -> &[MemoryCopy] {
+        &self.copies
+    }
+
+    /// Consumes the inspector and returns all recorded copies in execution order.
+    pub fn into_memory_copies(self) -> Vec<MemoryCopy> {
+        self.copies
+    }
+
+    /// Clears all recorded copies, so the inspector can be reused for the next transaction.
+    pub fn reset(&mut self) {
+        self.copies.clear();
+    }
+}
+
+impl<CTX> Inspector<CTX> for MemoryCopyInspector {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        let op = interp.bytecode.opcode();
+        let (code_address, first_operand) = match op {
+            opcode::MCOPY | opcode::CALLDATACOPY | opcode::CODECOPY | opcode::RETURNDATACOPY => {
+                (None, 0)
+            }
+            opcode::EXTCODECOPY => {
+                let Ok(address) = interp.stack.peek(0) else { return };
+                (Some(Address::from_word(B256::from(address.to_be_bytes()))), 1)
+            }
+            _ => return,
+        };
+        let (Ok(dest_offset), Ok(src_offset), Ok(len)) = (
+            interp.stack.peek(first_operand),
+            interp.stack.peek(first_operand + 1),
+            interp.stack.peek(first_operand + 2),
+        ) else {
+            return;
+        };
+
+        let Some(opcode) = OpCode::new(op) else { return };
+        self.copies.push(MemoryCopy {
+            opcode,
+            address: interp.input.target_address(),
+            code_address,
+            dest_offset,
+            src_offset,
+            len,
+        });
+    }
+}
@@ -0,0 +1,83 @@
+use alloc::vec::Vec;
+use alloy_primitives::Address;
+use revm::{
+    bytecode::opcode::OpCode,
+    interpreter::{
+        gas::memory_gas,
+        interpreter_types::{InputsTr, Jumps, MemoryTr},
+        num_words, Interpreter,
+    },
+    Inspector,
+};
+
+/// An expansion of the memory by a single step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryExpansion {
+    /// The opcode that expanded the memory, e.g. `MSTORE` or `CALLDATACOPY`.
+    pub opcode: OpCode,
+    /// The address of the contract whose memory was expanded.
+    pub address: Address,
+    /// The program counter of the opcode.
+    pub pc: usize,
+    /// The size of the memory in words before the step.
+    pub words_before: usize,
+    /// The size of the memory in words after the step.
+    pub words_after: usize,
+    /// The gas charged for the expansion, the part of the gas cost of the opcode that depends on
+    /// the memory size.
+    pub gas: u64,
+}
+
+/// An [Inspector] that isolates the gas paid for expanding the memory from the rest of the gas
+/// costs of the opcodes.
+///
+/// The expansion gas is derived from the number of words the memory grew by: the memory of `n`
+/// words costs `3 * n + n * n / 512`, and each step pays the difference to the previous size.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryExpansionInspector {
+    /// The step that is currently executed, with the memory size in words before it.
+    pending: Option<(OpCode, usize, usize)>,
+    /// All recorded expansions
+    expansions: Vec<MemoryExpansion>,
+}
+
+impl MemoryExpansionInspector {
+    /// Returns all steps that expanded the memory in execution order.
+    pub fn memory_expansions(&self) -> &[MemoryExpansion] {
+        &self.expansions
+    }
+
+    /// Returns the total gas paid for memory expansion.
+    pub fn memory_expansion_gas(&self) -> u64 {
+        self.expansions.iter().map(|expansion| expansion.gas).sum()
+    }
+
+    /// Clears all recorded expansions, so the inspector can be reused for the next transaction.
+    pub fn reset(&mut self) {
+        self.pending = None;
+        self.expansions.clear();
+    }
+}
+
+impl<CTX> Inspector<CTX> for MemoryExpansionInspector {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        let Some(opcode) = OpCode::new(interp.bytecode.opcode()) else { return };
+        self.pending = Some((opcode, interp.bytecode.pc(), num_words(interp.memory.size())));
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        let Some((opcode, pc, words_before)) = self.pending.take() else { return };
+        let words_after = num_words(interp.memory.size());
+        if words_after <= words_before {
+            return;
+        }
+        self.expansions.push(MemoryExpansion {
+            opcode,
+            address: interp.input.target_address(),
+            pc,
+            words_before,
+            words_after,
+            gas: memory_gas(words_after) - memory_gas(words_before),
+        });
+    }
+}
@@ -0,0 +1,93 @@
+use alloc::vec::Vec;
+use alloy_primitives::Address;
+use revm::{
+    interpreter::{CallInputs, CallOutcome, CreateInputs, CreateOutcome},
+    Inspector,
+};
+
+/// A re-entry into the target contract of a [ReentrancyInspector].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReentrancyEvent {
+    /// The call depth of the call that re-entered the target.
+    pub depth: usize,
+    /// The caller that re-entered the target.
+    pub caller: Address,
+}
+
+/// An [Inspector] that records every call into a target contract while a prior invocation of
+/// it is still on the call stack.
+///
+/// Calls are attributed to the account whose storage they execute in, so a `DELEGATECALL` from
+/// the target back into itself is a re-entry, while a `DELEGATECALL` from the target into a
+/// library is not.
+#[derive(Clone, Debug)]
+pub struct ReentrancyInspector {
+    /// The contract to detect re-entries into.
+    target: Address,
+    /// Whether each active call or create frame executes in the target.
+    frames: Vec<bool>,
+    /// The number of active call frames that execute in the target.
+    active: usize,
+    /// All recorded re-entries.
+    reentrancies: Vec<ReentrancyEvent>,
+}
+
+impl ReentrancyInspector {
+    /// Creates a new inspector that detects re-entries into the given contract.
+    pub const fn new(target: Address) -> Self {
+        Self { target, frames: Vec::new(), active: 0, reentrancies: Vec::new() }
+    }
+
+    /// Returns the contract this inspector detects re-entries into.
+    pub const fn target(&self) -> Address {
+        self.target
+    }
+
+    /// Returns all re-entries into the target, in execution order.
+    pub fn reentrancies(&self) -> &[ReentrancyEvent] {
+        &self.reentrancies
+    }
+
+    /// Clears all recorded re-entries, so the inspector can be reused for the next transaction.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.active = 0;
+        self.reentrancies.clear();
+    }
+}
+
+impl<CTX> Inspector<CTX> for ReentrancyInspector {
+    fn call(&mut self, _context: &mut CTX, inputs: &mut CallInputs) -> Option<CallOutcome> {
+        let is_target = inputs.target_address == self.target;
+        if is_target {
+            if self.active > 0 {
+                self.reentrancies
+                    .push(ReentrancyEvent { depth: self.frames.len(), caller: inputs.caller });
+            }
+            self.active += 1;
+        }
+        self.frames.push(is_target);
+        None
+    }
+
+    fn call_end(&mut self, _context: &mut CTX, _inputs: &CallInputs, _outcome: &mut CallOutcome) {
+        if self.frames.pop() == Some(true) {
+            self.active -= 1;
+        }
+    }
+
+    fn create(&mut self, _context: &mut CTX, _inputs: &mut CreateInputs) -> Option<CreateOutcome> {
+        // the init code runs in the new contract, so it never re-enters the target
+        self.frames.push(false);
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut CTX,
+        _inputs: &CreateInputs,
+        _outcome: &mut CreateOutcome,
+    ) {
+        self.frames.pop();
+    }
+}
@@ -50,6 +50,11 @@ impl StorageInspector {
     pub fn into_accessed_slots(self) -> HashMap<Address, HashMap<B256, u64>> {
         self.accessed_slots
     }
+
+    /// Clears all tracked slots, so the inspector can be reused for the next transaction.
+    pub fn reset(&mut self) {
+        self.accessed_slots.clear();
+    }
 }
 
 impl<CTX> Inspector<CTX> for StorageInspector
@@ -1,6 +1,19 @@
-use super::types::{CallTrace, CallTraceNode, TraceMemberOrder};
-use alloc::vec::Vec;
-use alloy_primitives::Address;
+use super::{
+    types::{
+        CallKind, CallTrace, CallTraceNode, DecodedCallData, GasForward, TraceError, TraceEvent,
+        TraceMemberOrder,
+    },
+    utils,
+};
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use alloy_json_abi::Function;
+use alloy_primitives::{
+    hex,
+    map::{HashMap, HashSet},
+    Address, Bytes, Selector, B256,
+};
+use core::{fmt::Write, mem};
+use revm::{bytecode::opcode, interpreter::gas};
 
 /// An arena of recorded traces.
 ///
@@ -53,6 +66,388 @@ impl CallTraceArena {
         self.nodes().iter().flat_map(|node| [node.trace.address, node.trace.caller].into_iter())
     }
 
+    /// Returns all addresses touched in the recorded traces.
+    ///
+    /// In addition to the [trace addresses](Self::trace_addresses), this includes the
+    /// beneficiaries of selfdestructs and the accounts inspected with `BALANCE`, `EXTCODESIZE`,
+    /// `EXTCODECOPY` and `EXTCODEHASH`. The inspected accounts are only known if the steps are
+    /// recorded with stack snapshots.
+    pub fn touched_addresses(&self) -> HashSet<Address> {
+        let mut addresses = self.trace_addresses().collect::<HashSet<_>>();
+        for node in self.nodes() {
+            addresses.extend(node.trace.selfdestruct_refund_target);
+            addresses.extend(node.trace.steps.iter().filter_map(|step| {
+                if !matches!(
+                    step.op.get(),
+                    opcode::BALANCE
+                        | opcode::EXTCODESIZE
+                        | opcode::EXTCODECOPY
+                        | opcode::EXTCODEHASH
+                ) {
+                    return None;
+                }
+                let item = step.stack.as_ref()?.last()?;
+                Some(Address::from_word(B256::from(item.to_be_bytes())))
+            }));
+        }
+        addresses
+    }
+
+    /// Returns the addresses of all contracts created successfully, together with the index of the
+    /// frame that created them.
+    ///
+    /// The creating frame is the parent of the create frame, for a contract creation transaction
+    /// it's the create frame itself.
+    pub fn created_contracts(&self) -> Vec<(usize, Address)> {
+        self.arena
+            .iter()
+            .filter(|node| node.kind().is_any_create() && node.trace.success)
+            .map(|node| (node.parent.unwrap_or(node.idx), node.trace.address))
+            .collect()
+    }
+
+    /// Returns the init code and the runtime code of all contracts created successfully, keyed
+    /// by the address of the created contract.
+    ///
+    /// The runtime code is the output of the create frame. Both are empty if only the sizes of
+    /// the data are recorded, see [`DataCaptureMode`](crate::tracing::DataCaptureMode).
+    pub fn deployed_code(&self) -> HashMap<Address, (Bytes, Bytes)> {
+        self.arena
+            .iter()
+            .filter(|node| node.kind().is_any_create() && node.trace.success)
+            .map(|node| (node.trace.address, (node.trace.data.clone(), node.trace.output.clone())))
+            .collect()
+    }
+
+    /// Returns the gas forwarded to each call made by a call opcode in the order the calls
+    /// started, so the forwarded gas can be checked against [`GasForward::expected_gas`].
+    pub fn gas_forwarding(&self) -> Vec<GasForward> {
+        self.arena
+            .iter()
+            .filter_map(|node| {
+                let trace = &node.trace;
+                let stipend = if matches!(trace.kind, CallKind::Call | CallKind::CallCode)
+                    && !trace.value.is_zero()
+                {
+                    gas::CALL_STIPEND
+                } else {
+                    0
+                };
+                Some(GasForward {
+                    idx: node.idx,
+                    caller_gas: trace.caller_gas?,
+                    requested_gas: trace.requested_gas?,
+                    stipend,
+                    gas_given: trace.gas_limit,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the deepest call that the reverted call at `idx` forwarded its revert from.
+    ///
+    /// A revert is considered forwarded from a reverted subcall if the output is empty or the
+    /// same.
+    pub fn revert_origin(&self, idx: usize) -> Option<&CallTraceNode> {
+        revert_origin(&self.arena, idx)
+    }
+
+    /// Returns the index of the frame that emitted the log at the given index in the logs of the
+    /// transaction receipt.
+    ///
+    /// The logs of reverted frames, including the frames of a reverted subtree, are not part of
+    /// the receipt and are skipped. This requires the logs to be recorded, see
+    /// [TracingInspectorConfig::record_logs](super::TracingInspectorConfig::record_logs).
+    pub fn frame_for_log(&self, log_index: usize) -> Option<usize> {
+        let is_reverted = |mut idx: usize| loop {
+            let node = &self.arena[idx];
+            if !node.trace.success {
+                return true;
+            }
+            match node.parent {
+                Some(parent) => idx = parent,
+                None => return false,
+            }
+        };
+        let mut logs = self
+            .arena
+            .iter()
+            .filter(|node| !is_reverted(node.idx))
+            .flat_map(|node| node.logs.iter().map(|log| (log.index, node.idx)))
+            .collect::<Vec<_>>();
+        logs.sort_unstable();
+        logs.get(log_index).map(|(_, idx)| *idx)
+    }
+
+    /// Returns the index and the address of all calls made to accounts without code, e.g. to
+    /// externally owned accounts, see [CallTrace::is_empty_code].
+    ///
+    /// The root call is not included, since a transaction to an externally owned account is a
+    /// regular transfer.
+    pub fn calls_to_empty_accounts(&self) -> Vec<(usize, Address)> {
+        self.arena
+            .iter()
+            .filter(|node| node.parent.is_some() && node.trace.is_empty_code)
+            .map(|node| (node.idx, node.trace.address))
+            .collect()
+    }
+
+    /// Returns the indices of all frames that ran out of gas.
+    pub fn out_of_gas_frames(&self) -> Vec<usize> {
+        self.arena
+            .iter()
+            .filter(|node| node.trace.error().is_some_and(|err| err.is_out_of_gas()))
+            .map(|node| node.idx)
+            .collect()
+    }
+
+    /// Returns true if any top-level call ran out of gas.
+    ///
+    /// Unlike [CallTraceArena::out_of_gas_frames], this ignores subcalls that ran out of gas if
+    /// their caller handled the failure.
+    pub fn is_out_of_gas(&self) -> bool {
+        self.roots().any(|node| node.trace.error().is_some_and(|err| err.is_out_of_gas()))
+    }
+
+    /// Returns the total gas refund of the recorded calls.
+    ///
+    /// This is the sum of the refunds of all top-level calls, each of which includes the refunds
+    /// of its successful subcalls.
+    #[inline]
+    pub fn total_refund(&self) -> u64 {
+        self.roots().map(|node| node.trace.refund).sum()
+    }
+
+    /// Returns the return data of the root call, or the runtime bytecode if the transaction
+    /// created a contract.
+    ///
+    /// Returns `None` if the root call didn't complete yet. Like the output of every call this is
+    /// empty if the data is not captured, see [DataCaptureMode](super::DataCaptureMode).
+    pub fn top_level_output(&self) -> Option<&Bytes> {
+        self.arena.first().filter(|node| node.trace.status.is_some()).map(|node| &node.trace.output)
+    }
+
+    /// Returns all top-level nodes of the arena.
+    ///
+    /// This is only the root call, unless other arenas were [appended](Self::append).
+    pub fn roots(&self) -> impl Iterator<Item = &CallTraceNode> + '_ {
+        self.nodes().iter().filter(|node| node.parent.is_none())
+    }
+
+    /// Appends all nodes of the `other` arena to this arena.
+    ///
+    /// The node indices of the `other` arena are re-based, so its root node becomes an
+    /// additional top-level node of this arena, and the log indices are shifted to follow the
+    /// logs of this arena. This is useful to combine the traces of multiple transactions.
+    pub fn append(&mut self, other: Self) {
+        let offset = self.arena.len();
+        let log_offset = self.arena.iter().map(|node| node.log_count()).sum::<usize>() as u64;
+
+        self.arena.extend(other.arena.into_iter().map(|mut node| {
+            node.idx += offset;
+            node.parent = node.parent.map(|parent| parent + offset);
+            node.children.iter_mut().for_each(|child| *child += offset);
+            node.logs.iter_mut().for_each(|log| log.index += log_offset);
+            node
+        }));
+    }
+
+    /// Returns the gas used by each contract, see [DelegateGasAttribution::CodeAddress].
+    ///
+    /// See [CallTraceArena::gas_by_address_with].
+    pub fn gas_by_address(&self) -> HashMap<Address, u64> {
+        self.gas_by_address_with(DelegateGasAttribution::CodeAddress)
+    }
+
+    /// Returns the gas used by each contract.
+    ///
+    /// Each call is only attributed its own gas, excluding the gas used by its subcalls, so the
+    /// sum of all values is the gas used by the top-level calls. The gas of delegate calls is
+    /// attributed according to the given [DelegateGasAttribution].
+    pub fn gas_by_address_with(
+        &self,
+        attribution: DelegateGasAttribution,
+    ) -> HashMap<Address, u64> {
+        // Note: this uses the parent instead of the children of a node, because calls to
+        // precompiles aren't attached to the parent if they are excluded
+        let mut subcalls_gas = vec![0u64; self.arena.len()];
+        for node in &self.arena {
+            if let Some(parent) = node.parent {
+                subcalls_gas[parent] += node.trace.gas_used;
+            }
+        }
+
+        let mut gas = HashMap::<Address, u64>::default();
+        for node in &self.arena {
+            let address = match attribution {
+                DelegateGasAttribution::CodeAddress => node.trace.address,
+                DelegateGasAttribution::StorageAddress => node.execution_address(),
+            };
+            *gas.entry(address).or_default() +=
+                node.trace.gas_used.saturating_sub(subcalls_gas[node.idx]);
+        }
+        gas
+    }
+
+    /// Decodes the call data of all calls with the functions returned by the given `resolver`.
+    ///
+    /// Each call whose selector is resolved is annotated with the function signature and the
+    /// decoded arguments in its [`DecodedCallTrace`](super::types::DecodedCallTrace). If the
+    /// arguments can't be decoded, the raw argument data is used as the only argument instead.
+    /// Calls whose selector is unknown, as well as contract creations, keep their raw call data.
+    pub fn decode_calls<F>(&mut self, resolver: F)
+    where
+        F: Fn(Selector) -> Option<Function>,
+    {
+        for node in &mut self.arena {
+            if node.kind().is_any_create() || node.trace.data.len() < 4 {
+                continue;
+            }
+            let (selector, data) = node.trace.data.split_at(4);
+            let Some(function) = resolver(Selector::from_slice(selector)) else { continue };
+            let args = utils::decode_call_args(&function, data)
+                .unwrap_or_else(|_| vec![hex::encode_prefixed(data)]);
+            node.trace.decoded().call_data =
+                Some(DecodedCallData { signature: function.signature(), args });
+        }
+    }
+
+    /// Compares the frames of this arena with the frames of the `other` arena, ignoring the gas
+    /// used.
+    ///
+    /// See [CallTraceArena::diff_with].
+    pub fn diff(&self, other: &Self) -> Vec<TraceDiff> {
+        self.diff_with(other, false)
+    }
+
+    /// Compares the frames of this arena with the frames of the `other` arena, e.g. to detect
+    /// behavior changes between EVM versions or contract upgrades.
+    ///
+    /// The frames are compared by their index, and each frame reports differences in the target
+    /// address, the output and the error, as well as the gas used if `include_gas` is true.
+    pub fn diff_with(&self, other: &Self, include_gas: bool) -> Vec<TraceDiff> {
+        let mut diffs = Vec::new();
+        for (frame, (left, right)) in self.arena.iter().zip(&other.arena).enumerate() {
+            let (left, right) = (&left.trace, &right.trace);
+            if left.address != right.address {
+                diffs.push(TraceDiff::To { frame, left: left.address, right: right.address });
+            }
+            if include_gas && left.gas_used != right.gas_used {
+                diffs.push(TraceDiff::GasUsed {
+                    frame,
+                    left: left.gas_used,
+                    right: right.gas_used,
+                });
+            }
+            if left.output != right.output {
+                diffs.push(TraceDiff::Output {
+                    frame,
+                    left: left.output.clone(),
+                    right: right.output.clone(),
+                });
+            }
+            let (left_error, right_error) = (left.error(), right.error());
+            if left_error != right_error {
+                diffs.push(TraceDiff::Error { frame, left: left_error, right: right_error });
+            }
+        }
+
+        let in_left = self.arena.len() > other.arena.len();
+        let common = self.arena.len().min(other.arena.len());
+        let total = self.arena.len().max(other.arena.len());
+        diffs.extend((common..total).map(|frame| TraceDiff::MissingFrame { frame, in_left }));
+        diffs
+    }
+
+    /// Renders the call tree as a [Graphviz](https://graphviz.org) DOT digraph.
+    ///
+    /// Each call is a node labeled with its caller, callee and gas used, reverted calls are
+    /// colored red. Edges point from a call to its subcalls and are labeled with the order of the
+    /// subcall within its parent.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph calls {\n");
+        for node in &self.arena {
+            let trace = &node.trace;
+            let (status, color) =
+                if trace.success { ("", "") } else { ("\\nreverted", ", color=red") };
+            let _ = writeln!(
+                dot,
+                "    {} [label=\"{}→{}\\ngas: {}{status}\"{color}];",
+                node.idx, trace.caller, trace.address, trace.gas_used
+            );
+        }
+        for node in &self.arena {
+            for (order, child) in node.children.iter().enumerate() {
+                let _ = writeln!(dot, "    {} -> {child} [label=\"{}\"];", node.idx, order + 1);
+            }
+        }
+        dot.push('}');
+        dot
+    }
+
+    /// Serializes the arena to [MessagePack](https://msgpack.org), which is more compact than
+    /// JSON, e.g. to archive traces.
+    ///
+    /// The fields are serialized by name, so [Self::from_msgpack] restores an equal arena.
+    #[cfg(feature = "msgpack")]
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        rmp_serde::to_vec_named(self).expect("arena is serializable")
+    }
+
+    /// Deserializes an arena serialized with [Self::to_msgpack].
+    #[cfg(feature = "msgpack")]
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+
+    /// Consumes the arena and returns its calls, logs and steps as a flat list of events in
+    /// execution order.
+    ///
+    /// Each call is opened by a [TraceEvent::Enter] and closed by a [TraceEvent::Exit], with the
+    /// events of its logs, steps and subcalls in between, like the hooks of geth's tracers fire.
+    /// Steps are only included if they were recorded. The events of multiple top-level calls,
+    /// see [Self::roots], follow each other.
+    pub fn into_events(mut self) -> Vec<TraceEvent> {
+        let roots = self.roots().map(|node| node.idx).collect::<Vec<_>>();
+        let mut events = Vec::new();
+        for idx in roots {
+            self.collect_events(idx, &mut events);
+        }
+        events
+    }
+
+    fn collect_events(&mut self, idx: usize, events: &mut Vec<TraceEvent>) {
+        let node = &mut self.arena[idx];
+        let ordering = mem::take(&mut node.ordering);
+        let children = mem::take(&mut node.children);
+        let mut logs = mem::take(&mut node.logs).into_iter().map(Some).collect::<Vec<_>>();
+        let mut steps = mem::take(&mut node.trace.steps).into_iter().map(Some).collect::<Vec<_>>();
+        let exit = TraceEvent::Exit {
+            idx,
+            status: node.trace.status,
+            output: node.trace.output.clone(),
+            gas_used: node.trace.gas_used,
+        };
+        events.push(TraceEvent::Enter { idx, trace: Box::new(mem::take(&mut node.trace)) });
+
+        for item in ordering {
+            match item {
+                TraceMemberOrder::Log(i) => {
+                    if let Some(log) = logs[i].take() {
+                        events.push(TraceEvent::Log { idx, log });
+                    }
+                }
+                TraceMemberOrder::Step(i) => {
+                    if let Some(step) = steps[i].take() {
+                        events.push(TraceEvent::Step { idx, step: Box::new(step) });
+                    }
+                }
+                TraceMemberOrder::Call(i) => self.collect_events(children[i], events),
+            }
+        }
+        events.push(exit);
+    }
+
     /// Pushes a new trace into the arena, returning the trace ID
     ///
     /// This appends a new trace to the arena, and also inserts a new entry in the node's parent
@@ -101,6 +496,67 @@ impl CallTraceArena {
     }
 }
 
+/// How to attribute the gas of delegate calls in [CallTraceArena::gas_by_address_with].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DelegateGasAttribution {
+    /// Attribute the gas to the contract whose code is executed.
+    #[default]
+    CodeAddress,
+    /// Attribute the gas to the contract whose storage is used, which is the caller.
+    StorageAddress,
+}
+
+/// A difference between the frames of two arenas, see [CallTraceArena::diff].
+///
+/// Frames are identified by their index in the arenas, the `left` value is from the arena
+/// [CallTraceArena::diff] is called on and the `right` value from the other arena.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceDiff {
+    /// The frame only exists in one of the arenas.
+    MissingFrame {
+        /// The index of the frame.
+        frame: usize,
+        /// Whether the frame only exists in the `left` arena.
+        in_left: bool,
+    },
+    /// The frames have a different target address.
+    To {
+        /// The index of the frame.
+        frame: usize,
+        /// The target address in the `left` arena.
+        left: Address,
+        /// The target address in the `right` arena.
+        right: Address,
+    },
+    /// The frames used a different amount of gas.
+    GasUsed {
+        /// The index of the frame.
+        frame: usize,
+        /// The gas used in the `left` arena.
+        left: u64,
+        /// The gas used in the `right` arena.
+        right: u64,
+    },
+    /// The frames returned different data.
+    Output {
+        /// The index of the frame.
+        frame: usize,
+        /// The output in the `left` arena.
+        left: Bytes,
+        /// The output in the `right` arena.
+        right: Bytes,
+    },
+    /// The frames failed with a different error, or only one of them failed.
+    Error {
+        /// The index of the frame.
+        frame: usize,
+        /// The error in the `left` arena.
+        left: Option<TraceError>,
+        /// The error in the `right` arena.
+        right: Option<TraceError>,
+    },
+}
+
 /// How to push a trace into the arena
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum PushTraceKind {
@@ -117,3 +573,17 @@ impl PushTraceKind {
         matches!(self, Self::PushAndAttachToParent)
     }
 }
+
+/// Returns the deepest call that the reverted call at `idx` forwarded its revert from.
+///
+/// A revert is considered forwarded from a reverted subcall if the output is empty or the same.
+pub(crate) fn revert_origin(nodes: &[CallTraceNode], idx: usize) -> Option<&CallTraceNode> {
+    let trace = &nodes[idx].trace;
+    if !trace.is_revert() {
+        return None;
+    }
+    let child = nodes[idx].children.iter().rev().map(|child| &nodes[*child]).find(|child| {
+        child.trace.is_revert() && (trace.output.is_empty() || child.trace.output == trace.output)
+    })?;
+    Some(revert_origin(nodes, child.idx).unwrap_or(child))
+}
@@ -0,0 +1,120 @@
+//! Block trace builder
+use crate::tracing::{GethTraceBuilder, MuxInspector};
+use alloc::{collections::btree_map::Entry, vec, vec::Vec};
+use alloy_primitives::TxHash;
+use alloy_rpc_types_eth::TransactionInfo;
+use alloy_rpc_types_trace::geth::{DiffMode, GethTrace, PreStateConfig, PreStateFrame};
+use revm::{
+    context_interface::result::{HaltReasonTr, ResultAndState},
+    DatabaseRef,
+};
+
+/// A type for collecting the geth style traces of all transactions of a block, e.g. for
+/// `debug_traceBlock`.
+///
+/// The transactions are expected to be pushed in order, each traced with the same
+/// [MuxInspector] that is reset after each transaction.
+#[derive(Clone, Debug, Default)]
+pub struct BlockTraceBuilder {
+    /// The traces of all pushed transactions.
+    traces: Vec<(TxHash, GethTrace)>,
+    /// The accumulated state diff of all pushed transactions, if enabled.
+    state_diff: Option<DiffMode>,
+}
+
+impl BlockTraceBuilder {
+    /// Returns a new, empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also accumulates the state diff of all pushed transactions, see
+    /// [BlockTraceBuilder::state_diff].
+    pub fn with_state_diff(mut self) -> Self {
+        self.state_diff = Some(DiffMode::default());
+        self
+    }
+
+    /// Adds the trace of the next transaction of the block and resets the `inspector` for the
+    /// next transaction.
+    ///
+    /// The `db` must be the state before the transaction, i.e. before the `result` is committed.
+    ///
+    /// The trace is only recorded if `tx_info` has a hash, the state diff is accumulated either
+    /// way.
+    pub fn push_transaction<DB: DatabaseRef>(
+        &mut self,
+        inspector: &mut MuxInspector,
+        result: &ResultAndState<impl HaltReasonTr>,
+        db: &DB,
+        tx_info: TransactionInfo,
+    ) -> Result<(), DB::Error> {
+        let trace = inspector.try_into_geth_trace(result, db, tx_info)?;
+        inspector.fuse();
+
+        if let Some(state_diff) = &mut self.state_diff {
+            let config = PreStateConfig { diff_mode: Some(true), ..Default::default() };
+            if let PreStateFrame::Diff(diff) =
+                GethTraceBuilder::new(vec![]).geth_prestate_traces(result, &config, db)?
+            {
+                merge_state_diff(state_diff, diff);
+            }
+        }
+
+        if let Some(hash) = tx_info.hash {
+            self.traces.push((hash, trace));
+        }
+        Ok(())
+    }
+
+    /// Returns the traces of all pushed transactions, in order.
+    pub fn traces(&self) -> &[(TxHash, GethTrace)] {
+        &self.traces
+    }
+
+    /// Returns the accumulated state diff of all pushed transactions, if enabled via
+    /// [BlockTraceBuilder::with_state_diff].
+    ///
+    /// The pre state of an account is its state before the first transaction that changed it.
+    pub fn state_diff(&self) -> Option<&DiffMode> {
+        self.state_diff.as_ref()
+    }
+
+    /// Consumes the builder and returns the traces of all pushed transactions, in order.
+    pub fn into_traces(self) -> Vec<(TxHash, GethTrace)> {
+        self.traces
+    }
+}
+
+/// Merges the state diff of the next transaction into the accumulated state diff of the block.
+fn merge_state_diff(block: &mut DiffMode, tx: DiffMode) {
+    for (address, pre) in tx.pre {
+        match block.pre.entry(address) {
+            Entry::Vacant(entry) => {
+                // accounts created in an earlier transaction have no pre state
+                if !block.post.contains_key(&address) {
+                    entry.insert(pre);
+                }
+            }
+            Entry::Occupied(mut entry) => {
+                // the pre state of the earlier transaction is complete, only the slots it didn't
+                // touch are missing
+                for (slot, value) in pre.storage {
+                    entry.get_mut().storage.entry(slot).or_insert(value);
+                }
+            }
+        }
+        if !tx.post.contains_key(&address) {
+            // the account was destroyed
+            block.post.remove(&address);
+        }
+    }
+
+    for (address, post) in tx.post {
+        let state = block.post.entry(address).or_default();
+        state.balance = post.balance.or(state.balance);
+        state.nonce = post.nonce.or(state.nonce);
+        state.code = post.code.or(state.code.take());
+        state.storage.extend(post.storage);
+    }
+}
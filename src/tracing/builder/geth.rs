@@ -6,11 +6,13 @@ use crate::tracing::{
 use alloc::{
     borrow::Cow,
     collections::{BTreeMap, VecDeque},
-    format, vec,
+    format,
+    string::{String, ToString},
+    vec,
     vec::Vec,
 };
 use alloy_primitives::{
-    map::{Entry, HashMap},
+    map::{Entry, HashMap, HashSet},
     Address, Bytes, B256, U256,
 };
 use alloy_rpc_types_trace::geth::{
@@ -31,19 +33,88 @@ use revm::{
 pub struct GethTraceBuilder<'a> {
     /// Recorded trace nodes.
     nodes: Cow<'a, [CallTraceNode]>,
+    /// How many opcodes a `GAS` may precede a call by to be treated as forwarding its gas, see
+    /// [`Self::with_gas_call_window`].
+    gas_call_window: usize,
+    /// The maximum length of a recorded keccak preimage, see
+    /// [`Self::with_max_keccak_preimage_len`].
+    max_keccak_preimage_len: Option<usize>,
+    /// The maximum number of distinct keccak preimages recorded per frame, see
+    /// [`Self::with_max_keccak_preimages`].
+    max_keccak_preimages: Option<usize>,
+    /// How the stack items of [`Self::geth_traces_json`] are encoded.
+    stack_encoding: StackEncoding,
 }
 
 impl GethTraceBuilder<'static> {
     /// Returns a new instance of the builder from [`Cow::Owned`]
     pub fn new(nodes: Vec<CallTraceNode>) -> GethTraceBuilder<'static> {
-        Self { nodes: Cow::Owned(nodes) }
+        Self {
+            nodes: Cow::Owned(nodes),
+            gas_call_window: 0,
+            max_keccak_preimage_len: None,
+            max_keccak_preimages: None,
+            stack_encoding: StackEncoding::FullHex,
+        }
     }
 }
 
 impl<'a> GethTraceBuilder<'a> {
     /// Returns a new instance of the builder from [`Cow::Borrowed`]
     pub fn new_borrowed(nodes: &'a [CallTraceNode]) -> GethTraceBuilder<'a> {
-        Self { nodes: Cow::Borrowed(nodes) }
+        Self {
+            nodes: Cow::Borrowed(nodes),
+            gas_call_window: 0,
+            max_keccak_preimage_len: None,
+            max_keccak_preimages: None,
+            stack_encoding: StackEncoding::FullHex,
+        }
+    }
+
+    /// Sets the look-behind window for `GAS` opcodes in [ERC-7562](Self::geth_erc7562_traces)
+    /// traces.
+    ///
+    /// A `GAS` that is followed by a `CALL`, `CALLCODE`, `DELEGATECALL` or `STATICCALL` within
+    /// the given number of (non-ignored) opcodes only forwards the gas to the call and is not
+    /// counted in the `used_opcodes`. E.g. a window of `1` matches `GAS; CALL`, a window of `2`
+    /// also matches `GAS; DUP1; CALL`.
+    ///
+    /// Defaults to `0`, which counts every `GAS`.
+    pub const fn with_gas_call_window(mut self, gas_call_window: usize) -> Self {
+        self.gas_call_window = gas_call_window;
+        self
+    }
+
+    /// Sets the maximum length of the `keccak` preimages in [ERC-7562](Self::geth_erc7562_traces)
+    /// traces.
+    ///
+    /// A `KECCAK256` of more bytes is still counted in the `used_opcodes`, but its preimage is not
+    /// recorded. This bounds the size of the trace if a contract hashes large memory regions.
+    ///
+    /// Defaults to `None`, which records all preimages.
+    pub const fn with_max_keccak_preimage_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_keccak_preimage_len = max_len;
+        self
+    }
+
+    /// Sets the maximum number of distinct `keccak` preimages recorded per frame in
+    /// [ERC-7562](Self::geth_erc7562_traces) traces.
+    ///
+    /// Once the limit is reached, only preimages that were already recorded in the frame are
+    /// recorded again.
+    ///
+    /// Defaults to `None`, which records all preimages.
+    pub const fn with_max_keccak_preimages(mut self, max_preimages: Option<usize>) -> Self {
+        self.max_keccak_preimages = max_preimages;
+        self
+    }
+
+    /// Sets how the stack items of the struct logs in [`Self::geth_traces_json`] are encoded.
+    ///
+    /// Defaults to [`StackEncoding::FullHex`], like geth.
+    pub const fn with_stack_encoding(mut self, stack_encoding: StackEncoding) -> Self {
+        self.stack_encoding = stack_encoding;
+        self
     }
 
     /// Consumes the builder and returns the recorded trace nodes.
@@ -137,6 +208,26 @@ impl<'a> GethTraceBuilder<'a> {
         }
     }
 
+    /// Generate a geth-style trace like [`Self::geth_traces`] and serialize it to JSON, with the
+    /// stack items of the struct logs encoded according to [`Self::with_stack_encoding`].
+    pub fn geth_traces_json(
+        &self,
+        receipt_gas_used: u64,
+        return_value: Bytes,
+        opts: GethDefaultTracingOptions,
+    ) -> serde_json::Value {
+        let frame = self.geth_traces(receipt_gas_used, return_value, opts);
+        let mut value = serde_json::to_value(&frame).expect("frame is serializable");
+        if let Some(serde_json::Value::Array(logs)) = value.get_mut("structLogs") {
+            for (log, value) in frame.struct_logs.iter().zip(logs) {
+                if let Some(stack) = self.stack_encoding.encode_stack(log) {
+                    value["stack"] = stack.into();
+                }
+            }
+        }
+        value
+    }
+
     /// Generate a geth-style traces for the call tracer.
     ///
     /// This decodes all call frames from the recorded traces.
@@ -152,7 +243,7 @@ impl<'a> GethTraceBuilder<'a> {
         let include_logs = opts.with_log.unwrap_or_default();
         // first fill up the root
         let main_trace_node = &self.nodes[0];
-        let mut root_call_frame = main_trace_node.geth_empty_call_frame(include_logs);
+        let mut root_call_frame = self.geth_call_frame(main_trace_node, include_logs);
         root_call_frame.gas_used = U256::from(gas_used);
 
         // selfdestructs are not recorded as individual call traces but are derived from
@@ -172,9 +263,7 @@ impl<'a> GethTraceBuilder<'a> {
         call_frames.push((0, root_call_frame));
 
         for (idx, trace) in self.nodes.iter().enumerate().skip(1) {
-            // include logs only if call and all its parents were successful
-            let include_logs = include_logs && !self.call_or_parent_failed(trace);
-            call_frames.push((idx, trace.geth_empty_call_frame(include_logs)));
+            call_frames.push((idx, self.geth_call_frame(trace, include_logs)));
 
             // selfdestructs are not recorded as individual call traces but are derived from
             // the call trace and are added as additional `CallFrame` objects
@@ -203,6 +292,14 @@ impl<'a> GethTraceBuilder<'a> {
         }
     }
 
+    /// Converts the given trace into an _empty_ geth [CallFrame], shared by all call tracers.
+    ///
+    /// Like geth, logs are only included if the call and all its parents were successful, since
+    /// the logs of failed calls are discarded.
+    fn geth_call_frame(&self, node: &CallTraceNode, with_log: bool) -> CallFrame {
+        node.geth_empty_call_frame(with_log && !self.call_or_parent_failed(node))
+    }
+
     /// Returns true if the given trace or any of its parents failed.
     fn call_or_parent_failed(&self, node: &CallTraceNode) -> bool {
         if node.trace.is_error() {
@@ -386,19 +483,36 @@ impl<'a> GethTraceBuilder<'a> {
             let mut contract_size = HashMap::default();
             let mut ext_code_access_info = Vec::new();
             let mut keccak = Vec::new();
+            // the distinct preimages are only needed to enforce a limit
+            let mut distinct_keccak =
+                self.max_keccak_preimages.map(|max| (max, HashSet::<Bytes>::default()));
             let mut out_of_gas = false;
 
-            for step in &trace.steps {
+            // Skip ignored opcodes
+            let steps = trace
+                .steps
+                .iter()
+                .filter(|step| !opts.ignored_opcodes.contains(&step.op.get()))
+                .collect::<Vec<_>>();
+
+            for (step_idx, step) in steps.iter().enumerate() {
                 let op = step.op.get();
 
-                // Skip if opcode is ignored
-                if opts.ignored_opcodes.contains(&op) {
-                    continue;
+                // Count used opcodes, except for a `GAS` that only forwards the gas to a call
+                let is_gas_forwarded = op == opcode::GAS
+                    && steps[step_idx + 1..].iter().take(self.gas_call_window).any(|step| {
+                        matches!(
+                            step.op.get(),
+                            opcode::CALL
+                                | opcode::CALLCODE
+                                | opcode::DELEGATECALL
+                                | opcode::STATICCALL
+                        )
+                    });
+                if !is_gas_forwarded {
+                    *used_opcodes.entry(op).or_insert(0) += 1;
                 }
 
-                // Count used opcodes
-                *used_opcodes.entry(op).or_insert(0) += 1;
-
                 // Accessed storage slots
                 match op {
                     opcode::SLOAD => {
@@ -452,7 +566,7 @@ impl<'a> GethTraceBuilder<'a> {
                 if matches!(op, opcode::EXTCODESIZE | opcode::EXTCODECOPY | opcode::EXTCODEHASH) {
                     if let Some(stack) = &step.stack {
                         if let Some(item) = stack.get(stack.len().saturating_sub(1)) {
-                            let address = Address::from(item.to_be_bytes());
+                            let address = Address::from_word(B256::from(item.to_be_bytes()));
                             ext_code_access_info.push(format!("{address:?}"));
                             if let Entry::Vacant(e) = contract_size.entry(address) {
                                 if let Ok(Some(account)) = db.basic_ref(address) {
@@ -481,13 +595,26 @@ impl<'a> GethTraceBuilder<'a> {
                             if let (Ok(offset), Ok(len)) =
                                 (usize::try_from(offset), usize::try_from(len))
                             {
-                                let mut data = vec![0; len];
-                                if offset < memory.0.len() {
-                                    let end = (offset + len).min(memory.0.len());
-                                    let copy_len = end - offset;
-                                    data[..copy_len].copy_from_slice(&memory.0[offset..end]);
+                                if self.max_keccak_preimage_len.is_none_or(|max| len <= max) {
+                                    let mut data = vec![0; len];
+                                    if offset < memory.0.len() {
+                                        let end = (offset + len).min(memory.0.len());
+                                        let copy_len = end - offset;
+                                        data[..copy_len].copy_from_slice(&memory.0[offset..end]);
+                                    }
+                                    let data = Bytes::from(data);
+                                    let keep = match &mut distinct_keccak {
+                                        Some((max, distinct)) => {
+                                            distinct.contains(&data)
+                                                || (distinct.len() < *max
+                                                    && distinct.insert(data.clone()))
+                                        }
+                                        None => true,
+                                    };
+                                    if keep {
+                                        keccak.push(data);
+                                    }
                                 }
-                                keccak.push(Bytes::from(data));
                             }
                         }
                     }
@@ -497,8 +624,7 @@ impl<'a> GethTraceBuilder<'a> {
             let call_frame = if idx == 0 {
                 top_call.take().unwrap()
             } else {
-                let include_logs = include_logs && !self.call_or_parent_failed(node);
-                self.nodes[idx].geth_empty_call_frame(include_logs)
+                self.geth_call_frame(node, include_logs)
             };
 
             let call_frame_type = Self::convert_call_kind(node.kind());
@@ -516,7 +642,13 @@ impl<'a> GethTraceBuilder<'a> {
                     error: call_frame.error,
                     revert_reason: call_frame.revert_reason,
                     logs: call_frame.logs,
-                    value: call_frame.value,
+                    // a DELEGATECALL inherits the value of its parent, a STATICCALL can't transfer
+                    // any value
+                    value: if node.kind() == CallKind::StaticCall {
+                        Some(U256::ZERO)
+                    } else {
+                        call_frame.value
+                    },
                     accessed_slots,
                     ext_code_access_info,
                     used_opcodes,
@@ -555,3 +687,32 @@ impl<'a> GethTraceBuilder<'a> {
         }
     }
 }
+
+/// How the stack items of a [StructLog] are encoded, see
+/// [`GethTraceBuilder::with_stack_encoding`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StackEncoding {
+    /// Zero padded 32-byte hex, e.g. `0x0000...01`, as emitted by geth.
+    #[default]
+    FullHex,
+    /// Hex without leading zeros, e.g. `0x1`.
+    MinimalHex,
+    /// Decimal, e.g. `1`.
+    Decimal,
+}
+
+impl StackEncoding {
+    /// Encodes a single stack item.
+    pub fn encode(&self, value: U256) -> String {
+        match self {
+            Self::FullHex => B256::from(value).to_string(),
+            Self::MinimalHex => format!("{value:#x}"),
+            Self::Decimal => value.to_string(),
+        }
+    }
+
+    /// Encodes the stack of the given [StructLog], if it was recorded.
+    pub fn encode_stack(&self, log: &StructLog) -> Option<Vec<String>> {
+        log.stack.as_ref().map(|stack| stack.iter().map(|value| self.encode(*value)).collect())
+    }
+}
@@ -1,5 +1,8 @@
 //! Builder types for building traces
 
+/// Geth style trace builder for all transactions of a block
+pub mod block;
+
 /// Geth style trace builders for `debug_` namespace
 pub mod geth;
 
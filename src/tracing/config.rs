@@ -76,10 +76,30 @@ pub struct TracingInspectorConfig {
     pub record_logs: bool,
     /// Whether to record immediate bytes for opcodes.
     pub record_immediate_bytes: bool,
+    /// Whether to only record the top-level call.
+    ///
+    /// If enabled, subcalls and everything that happens inside them (steps, logs) are not
+    /// recorded.
+    pub only_top_call: bool,
+    /// How much of the input and output data of calls to record.
+    pub data_capture: DataCaptureMode,
+    /// Whether to record the keccak256 hashes of the input and output data of calls.
+    pub record_data_hashes: bool,
+    /// The maximum number of call frames to record, including the top-level call.
+    ///
+    /// Once reached, further calls and everything inside them are not recorded. Unlike the
+    /// call depth this limits the total number of frames, regardless of their nesting.
+    pub max_frames: Option<usize>,
+    /// Whether to decode the inputs of calls to well-known precompiles, such as the lengths of
+    /// the `modexp` operands, see [PrecompileInput](crate::tracing::types::PrecompileInput).
+    pub record_precompile_inputs: bool,
 }
 
 impl TracingInspectorConfig {
     /// Returns a config with everything enabled.
+    ///
+    /// The hashes of the call data and the decoded precompile inputs are not recorded, see
+    /// [Self::data_hashes] and [Self::precompile_inputs].
     pub const fn all() -> Self {
         Self {
             record_steps: true,
@@ -91,6 +111,11 @@ impl TracingInspectorConfig {
             exclude_precompile_calls: false,
             record_logs: true,
             record_immediate_bytes: true,
+            only_top_call: false,
+            data_capture: DataCaptureMode::Full,
+            record_data_hashes: false,
+            max_frames: None,
+            record_precompile_inputs: false,
         }
     }
 
@@ -106,6 +131,11 @@ impl TracingInspectorConfig {
             record_logs: false,
             record_opcodes_filter: None,
             record_immediate_bytes: false,
+            only_top_call: false,
+            data_capture: DataCaptureMode::Full,
+            record_data_hashes: false,
+            max_frames: None,
+            record_precompile_inputs: false,
         }
     }
 
@@ -123,6 +153,11 @@ impl TracingInspectorConfig {
             record_logs: false,
             record_opcodes_filter: None,
             record_immediate_bytes: false,
+            only_top_call: false,
+            data_capture: DataCaptureMode::Full,
+            record_data_hashes: false,
+            max_frames: None,
+            record_precompile_inputs: false,
         }
     }
 
@@ -146,6 +181,12 @@ impl TracingInspectorConfig {
             .set_state_diffs(true)
     }
 
+    /// Returns the config for all of parity's `trace_replayTransaction` outputs, an alias of
+    /// [`Self::parity_vm_trace`], which also covers the call traces and state diffs.
+    pub const fn parity() -> Self {
+        Self::parity_vm_trace()
+    }
+
     /// Returns a config for geth style traces.
     ///
     /// This config does _not_ record opcode level traces and is suited for `debug_traceTransaction`
@@ -163,9 +204,23 @@ impl TracingInspectorConfig {
             record_logs: false,
             record_opcodes_filter: None,
             record_immediate_bytes: false,
+            only_top_call: false,
+            data_capture: DataCaptureMode::Full,
+            record_data_hashes: false,
+            max_frames: None,
+            record_precompile_inputs: false,
         }
     }
 
+    /// Returns a config for geth's [CallTracer](alloy_rpc_types_trace::geth::CallFrame) that
+    /// records only the call frames and their logs.
+    ///
+    /// No steps are recorded, which keeps the overhead minimal. Use
+    /// [`Self::from_geth_call_config`] to configure the logs and top call from a [CallConfig].
+    pub const fn call_tracer() -> Self {
+        Self::none().set_record_logs(true)
+    }
+
     /// Returns the [TracingInspectorConfig] depending on the enabled [TraceType]s
     ///
     /// Note: the parity statediffs can be populated entirely via the execution result, so we don't
@@ -195,6 +250,7 @@ impl TracingInspectorConfig {
                 StackSnapshotType::Full
             },
             record_state_diff: !config.disable_storage.unwrap_or_default(),
+            record_returndata_snapshots: config.enable_return_data.unwrap_or_default(),
             ..Self::default_geth()
         }
     }
@@ -208,6 +264,7 @@ impl TracingInspectorConfig {
         Self::none()
             // call tracer is similar parity tracer with optional support for logs
             .set_record_logs(config.with_log.unwrap_or_default())
+            .set_only_top_call(config.only_top_call.unwrap_or_default())
     }
 
     /// Returns a config for geth's
@@ -219,8 +276,9 @@ impl TracingInspectorConfig {
             .set_record_logs(config.with_log.unwrap_or_default())
             // need memory snapshots for keccak preimages
             .set_memory_snapshots(true)
-            // need stack snapshots for keccak preimages
-            .set_stack_snapshots(StackSnapshotType::Full)
+            // need stack snapshots for keccak preimages and the pushed values for the results of
+            // opcodes
+            .set_stack_snapshots(StackSnapshotType::All)
             .steps()
     }
 
@@ -259,6 +317,19 @@ impl TracingInspectorConfig {
         self.record_logs |= other.record_logs;
         self.record_opcodes_filter = self.record_opcodes_filter.or(other.record_opcodes_filter);
         self.record_immediate_bytes |= other.record_immediate_bytes;
+        // only skip subcalls if both configs skip them
+        self.only_top_call &= other.only_top_call;
+        // only drop the data if both configs drop it
+        if other.data_capture.is_full() {
+            self.data_capture = DataCaptureMode::Full;
+        }
+        self.record_data_hashes |= other.record_data_hashes;
+        self.record_precompile_inputs |= other.record_precompile_inputs;
+        // keep the higher limit, no limit at all if either config has none
+        self.max_frames = match (self.max_frames, other.max_frames) {
+            (Some(max), Some(other)) => Some(max.max(other)),
+            _ => None,
+        };
         self
     }
 
@@ -346,6 +417,25 @@ impl TracingInspectorConfig {
         self
     }
 
+    /// Disable recording of returndata buffer snapshots
+    pub const fn disable_returndata_snapshots(self) -> Self {
+        self.set_returndata_snapshots(false)
+    }
+
+    /// Enable recording of returndata buffer snapshots
+    pub const fn returndata_snapshots(self) -> Self {
+        self.set_returndata_snapshots(true)
+    }
+
+    /// Configure whether the tracer should record the returndata buffer available at each step.
+    ///
+    /// This is the data returned by the most recent call of the current context, which is what
+    /// `RETURNDATASIZE` and `RETURNDATACOPY` operate on.
+    pub const fn set_returndata_snapshots(mut self, record_returndata_snapshots: bool) -> Self {
+        self.record_returndata_snapshots = record_returndata_snapshots;
+        self
+    }
+
     /// Disable recording of individual logs
     pub const fn disable_record_logs(self) -> Self {
         self.set_record_logs(false)
@@ -373,6 +463,82 @@ impl TracingInspectorConfig {
         self.set_immediate_bytes(true)
     }
 
+    /// Configure whether only the top-level call should be recorded.
+    ///
+    /// If set to `true`, subcalls are not recorded at all, which mirrors geth's `onlyTopCall`
+    /// option of the call tracer.
+    pub const fn set_only_top_call(mut self, only_top_call: bool) -> Self {
+        self.only_top_call = only_top_call;
+        self
+    }
+
+    /// Only record the top-level call.
+    pub const fn only_top_call(self) -> Self {
+        self.set_only_top_call(true)
+    }
+
+    /// Configure how much of the input and output data of calls should be recorded.
+    pub const fn set_data_capture(mut self, data_capture: DataCaptureMode) -> Self {
+        self.data_capture = data_capture;
+        self
+    }
+
+    /// Only record the sizes of the input and output data of calls.
+    pub const fn size_only_data(self) -> Self {
+        self.set_data_capture(DataCaptureMode::SizeOnly)
+    }
+
+    /// Configure whether the keccak256 hashes of the input and output data of calls should be
+    /// recorded.
+    ///
+    /// The hashes are recorded regardless of the [DataCaptureMode], so identical calls can be
+    /// deduplicated without keeping the data itself.
+    pub const fn set_record_data_hashes(mut self, record_data_hashes: bool) -> Self {
+        self.record_data_hashes = record_data_hashes;
+        self
+    }
+
+    /// Record the keccak256 hashes of the input and output data of calls.
+    pub const fn data_hashes(self) -> Self {
+        self.set_record_data_hashes(true)
+    }
+
+    /// Configure the maximum number of call frames to record, see [Self::max_frames].
+    pub const fn set_max_frames(mut self, max_frames: Option<usize>) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// Record at most `max_frames` call frames, the top-level call is always recorded.
+    pub const fn with_max_frames(self, max_frames: usize) -> Self {
+        self.set_max_frames(Some(max_frames))
+    }
+
+    /// Configure whether the inputs of calls to well-known precompiles should be decoded, see
+    /// [Self::record_precompile_inputs].
+    pub const fn set_record_precompile_inputs(mut self, record_precompile_inputs: bool) -> Self {
+        self.record_precompile_inputs = record_precompile_inputs;
+        self
+    }
+
+    /// Decode the inputs of calls to well-known precompiles.
+    pub const fn precompile_inputs(self) -> Self {
+        self.set_record_precompile_inputs(true)
+    }
+
+    /// Configure the [OpcodeFilter] for the steps to record.
+    ///
+    /// If set, only steps with an opcode enabled in the filter are recorded.
+    pub const fn set_record_opcodes_filter(mut self, filter: Option<OpcodeFilter>) -> Self {
+        self.record_opcodes_filter = filter;
+        self
+    }
+
+    /// Only record steps with an opcode enabled in the given [OpcodeFilter].
+    pub const fn with_opcodes_filter(self, filter: OpcodeFilter) -> Self {
+        self.set_record_opcodes_filter(Some(filter))
+    }
+
     /// If [OpcodeFilter] is configured, returns whether the given opcode should be recorded.
     /// Otherwise, always returns true.
     #[inline]
@@ -415,6 +581,34 @@ impl StackSnapshotType {
     }
 }
 
+/// How much of the input and output data of calls to record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DataCaptureMode {
+    /// Record the full input and output data.
+    #[default]
+    Full,
+    /// Only record the sizes of the input and output data.
+    ///
+    /// The [`CallTrace::data`](crate::tracing::types::CallTrace::data) and
+    /// [`CallTrace::output`](crate::tracing::types::CallTrace::output) are left empty, which saves
+    /// memory for calls with large data.
+    SizeOnly,
+}
+
+impl DataCaptureMode {
+    /// Returns true if this is the [DataCaptureMode::Full] variant
+    #[inline]
+    pub const fn is_full(self) -> bool {
+        matches!(self, Self::Full)
+    }
+
+    /// Returns true if this is the [DataCaptureMode::SizeOnly] variant
+    #[inline]
+    pub const fn is_size_only(self) -> bool {
+        matches!(self, Self::SizeOnly)
+    }
+}
+
 /// What kind of tracing style this is.
 ///
 /// This affects things like error messages.
@@ -462,6 +656,22 @@ mod tests {
         assert!(!config.record_state_diff);
     }
 
+    #[test]
+    fn test_parity_preset() {
+        let config = TracingInspectorConfig::parity();
+        assert!(config.record_steps);
+        assert!(config.record_memory_snapshots);
+        assert!(config.record_stack_snapshots.is_pushes());
+        assert!(config.record_state_diff);
+        assert!(config.exclude_precompile_calls);
+
+        // geth only
+        assert!(!config.record_logs);
+        assert!(!config.record_returndata_snapshots);
+        assert!(!config.record_immediate_bytes);
+        assert!(!config.only_top_call);
+    }
+
     #[test]
     fn test_flat_call_config() {
         let config = FlatCallConfig { include_precompiles: Some(true), ..Default::default() };
@@ -0,0 +1,453 @@
+//! Helpers for validating recorded call traces against the
+//! [ERC-7562](https://eips.ethereum.org/EIPS/eip-7562) validation rules.
+//!
+//! Most helpers inspect the recorded steps and their stack snapshots, so the traces must be
+//! recorded with steps and [all](crate::tracing::StackSnapshotType::All) stack snapshots, e.g.
+//! with
+//! [TracingInspectorConfig::from_geth_erc7562_config](crate::tracing::TracingInspectorConfig::from_geth_erc7562_config).
+
+use crate::tracing::{
+    types::{CallTraceNode, TraceMemberOrder},
+    CallTraceArena,
+};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use alloy_primitives::{
+    map::{HashMap, HashSet},
+    Address, B256, U256,
+};
+use alloy_rpc_types_trace::geth::{
+    erc7562::{CallFrameType, Erc7562Frame},
+    CallFrame,
+};
+use revm::bytecode::opcode::{self, OpCode};
+use thiserror::Error;
+
+/// Opcodes whose result depends on the block the transaction is included in.
+///
+/// Validation code that uses any of these can behave differently in simulation than on chain.
+pub const BLOCK_DEPENDENT_OPCODES: [u8; 8] = [
+    opcode::NUMBER,
+    opcode::TIMESTAMP,
+    opcode::BLOCKHASH,
+    // PREVRANDAO since the merge
+    opcode::DIFFICULTY,
+    opcode::BASEFEE,
+    opcode::BLOBBASEFEE,
+    opcode::GASLIMIT,
+    opcode::COINBASE,
+];
+
+/// A violation of an ERC-7562 validation rule.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error(
+    "opcode 0x{opcode:02x} is not allowed in frame {frame} (depth {depth}, contract {address})"
+)]
+pub struct Erc7562Violation {
+    /// The opcode that is not allowed.
+    pub opcode: u8,
+    /// The index of the frame in the arena that executed the opcode.
+    pub frame: usize,
+    /// The depth of the frame that executed the opcode.
+    pub depth: usize,
+    /// The address of the contract whose code executed the opcode.
+    pub address: Address,
+}
+
+/// A read of a transient storage slot via `TLOAD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransientStorageRead {
+    /// The index of the frame in the arena that read the slot.
+    pub frame: usize,
+    /// The address of the contract whose transient storage was read.
+    pub address: Address,
+    /// The transient storage slot.
+    pub slot: U256,
+    /// The observed value of the slot.
+    pub value: U256,
+}
+
+/// A storage or transient storage access via `SLOAD`, `SSTORE`, `TLOAD` or `TSTORE`, see
+/// [CallTraceArena::storage_ops].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageOp {
+    /// The opcode that accessed the slot.
+    pub op: OpCode,
+    /// The address of the contract whose storage was accessed.
+    pub contract: Address,
+    /// The storage slot.
+    pub slot: U256,
+    /// The loaded value for `SLOAD` and `TLOAD`, the stored value for `SSTORE` and `TSTORE`.
+    pub value: U256,
+    /// The index of the frame in the arena that accessed the slot.
+    pub frame: usize,
+    /// The index of the step in the frame that accessed the slot.
+    pub step: usize,
+}
+
+/// Returns the `used_opcodes` of an [`Erc7562Frame`] keyed by the opcode mnemonics, e.g.
+/// `"SSTORE"`, instead of the opcode bytes.
+///
+/// Undefined opcodes are keyed by their hex value, e.g. `"0x0c"`.
+pub fn used_opcodes_named(used_opcodes: &HashMap<u8, u64>) -> HashMap<String, u64> {
+    used_opcodes
+        .iter()
+        .map(|(&op, &count)| {
+            let name = OpCode::new(op).map_or_else(|| format!("0x{op:02x}"), |op| op.to_string());
+            (name, count)
+        })
+        .collect()
+}
+
+/// Serializes an [`Erc7562Frame`] to JSON with the keys of all objects sorted.
+///
+/// The accessed slots, used opcodes and contract sizes of the frame are hash maps, so their
+/// serialized order differs between otherwise identical frames. Use this for stable output, e.g.
+/// to compare against fixtures or to cache traces.
+pub fn to_sorted_json(frame: &Erc7562Frame) -> serde_json::Value {
+    fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => {
+                let mut entries = map.into_iter().collect::<Vec<_>>();
+                entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                entries.into_iter().map(|(key, value)| (key, sort_keys(value))).collect()
+            }
+            serde_json::Value::Array(values) => values.into_iter().map(sort_keys).collect(),
+            value => value,
+        }
+    }
+    sort_keys(serde_json::to_value(frame).expect("frame is serializable"))
+}
+
+/// Converts an [`Erc7562Frame`] and its subcalls to the [`CallFrame`] of the call tracer.
+///
+/// This drops all ERC-7562 specific fields, such as the accessed slots and used opcodes, so both
+/// outputs can be obtained from a single ERC-7562 trace.
+pub fn into_call_frame(frame: Erc7562Frame) -> CallFrame {
+    let typ = match frame.call_frame_type {
+        CallFrameType::Call => "CALL",
+        CallFrameType::DelegateCall => "DELEGATECALL",
+        CallFrameType::CallCode => "CALLCODE",
+        CallFrameType::StaticCall => "STATICCALL",
+        CallFrameType::Create => "CREATE",
+        CallFrameType::Create2 => "CREATE2",
+    };
+    CallFrame {
+        from: frame.from,
+        gas: U256::from(frame.gas),
+        gas_used: U256::from(frame.gas_used),
+        to: frame.to,
+        input: frame.input,
+        output: frame.output,
+        error: frame.error,
+        revert_reason: frame.revert_reason,
+        calls: frame.calls.into_iter().map(into_call_frame).collect(),
+        logs: frame.logs,
+        value: frame.value,
+        typ: typ.to_string(),
+    }
+}
+
+impl CallTraceArena {
+    /// Checks that all recorded frames only executed opcodes of the given allow-list.
+    ///
+    /// Returns the first opcode, in execution order, that is not part of the allow-list.
+    ///
+    /// Note: this requires steps to be recorded, see
+    /// [TracingInspectorConfig::steps](crate::tracing::TracingInspectorConfig::steps).
+    pub fn validate_against_allowlist(
+        &self,
+        allowed: &HashSet<u8>,
+    ) -> Result<(), Erc7562Violation> {
+        self.roots().try_for_each(|root| self.validate_node_against_allowlist(root, allowed))
+    }
+
+    /// Returns all [block dependent opcodes](BLOCK_DEPENDENT_OPCODES) executed in any of the
+    /// recorded frames.
+    ///
+    /// Note: this requires steps to be recorded, see
+    /// [TracingInspectorConfig::steps](crate::tracing::TracingInspectorConfig::steps).
+    pub fn block_dependent_opcodes(&self) -> Vec<OpCode> {
+        let used_opcodes = self
+            .nodes()
+            .iter()
+            .flat_map(|node| node.trace.steps.iter().map(|step| step.op.get()))
+            .collect::<HashSet<_>>();
+
+        BLOCK_DEPENDENT_OPCODES
+            .into_iter()
+            .filter(|op| used_opcodes.contains(op))
+            .filter_map(OpCode::new)
+            .collect()
+    }
+
+    /// Returns all reads of transient storage slots, in the order of the recorded frames.
+    ///
+    /// Unlike the `transient_reads` of the ERC-7562 frame, this includes the observed value of
+    /// each read.
+    pub fn transient_reads(&self) -> Vec<TransientStorageRead> {
+        self.nodes()
+            .iter()
+            .flat_map(|node| {
+                node.step_results(opcode::TLOAD).filter_map(|(slot, value)| {
+                    Some(TransientStorageRead {
+                        frame: node.idx,
+                        address: node.execution_address(),
+                        slot: slot?,
+                        value,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Returns all transient storage slots accessed via `TLOAD` or `TSTORE`, grouped by the
+    /// address of the contract whose transient storage was accessed.
+    ///
+    /// Transient storage is only cleared at the end of the transaction, so this aggregates the
+    /// slots of all frames to reason about transient state shared between calls.
+    pub fn transient_slots_touched(&self) -> HashMap<Address, HashSet<U256>> {
+        let mut slots = HashMap::<Address, HashSet<U256>>::default();
+        for node in self.nodes() {
+            for step in &node.trace.steps {
+                if !matches!(step.op.get(), opcode::TLOAD | opcode::TSTORE) {
+                    continue;
+                }
+                if let Some(slot) = step.stack.as_ref().and_then(|stack| stack.last()) {
+                    slots.entry(node.execution_address()).or_default().insert(*slot);
+                }
+            }
+        }
+        slots
+    }
+
+    /// Returns all storage slots accessed via `SLOAD` or `SSTORE`, grouped by the address of the
+    /// contract whose storage was accessed.
+    ///
+    /// The slots accessed by a delegate call belong to the storage of the delegating contract,
+    /// see [CallTraceNode::execution_address], not the contract whose code is executed.
+    pub fn storage_slots_touched(&self) -> HashMap<Address, HashSet<U256>> {
+        let mut slots = HashMap::<Address, HashSet<U256>>::default();
+        for node in self.nodes() {
+            for step in &node.trace.steps {
+                if !matches!(step.op.get(), opcode::SLOAD | opcode::SSTORE) {
+                    continue;
+                }
+                if let Some(slot) = step.stack.as_ref().and_then(|stack| stack.last()) {
+                    slots.entry(node.execution_address()).or_default().insert(*slot);
+                }
+            }
+        }
+        slots
+    }
+
+    /// Returns all accesses of storage and transient storage slots in execution order.
+    ///
+    /// Unlike the `accessed_slots` of the ERC-7562 frame, this keeps the order and the values of
+    /// the accesses, e.g. to reconstruct the state transitions of a slot. As for
+    /// [CallTraceArena::storage_slots_touched], a delegate call accesses the storage of the
+    /// delegating contract.
+    pub fn storage_ops(&self) -> Vec<StorageOp> {
+        let mut ops = Vec::new();
+        for root in self.roots() {
+            self.collect_storage_ops(root, &mut ops);
+        }
+        ops
+    }
+
+    /// Returns all storage slots that are accessed via `SLOAD` or `SSTORE` by more than one
+    /// frame, together with the indices of the frames that accessed them.
+    ///
+    /// ERC-7562 restricts which entities may access the storage of a contract, so a slot shared
+    /// between the frames of different entities can violate the storage rules. The slots are
+    /// sorted by contract address and slot.
+    pub fn shared_slot_accesses(&self) -> Vec<(Address, U256, Vec<usize>)> {
+        let mut frames = BTreeMap::<(Address, U256), Vec<usize>>::new();
+        for node in self.nodes() {
+            for step in &node.trace.steps {
+                if !matches!(step.op.get(), opcode::SLOAD | opcode::SSTORE) {
+                    continue;
+                }
+                let Some(slot) = step.stack.as_ref().and_then(|stack| stack.last()) else {
+                    continue;
+                };
+                let accessed_by = frames.entry((node.execution_address(), *slot)).or_default();
+                if accessed_by.last() != Some(&node.idx) {
+                    accessed_by.push(node.idx);
+                }
+            }
+        }
+        frames
+            .into_iter()
+            .filter(|(_, frames)| frames.len() > 1)
+            .map(|((address, slot), frames)| (address, slot, frames))
+            .collect()
+    }
+
+    /// Returns the code hashes observed by `EXTCODEHASH` in each frame, indexed by the frame index
+    /// in the arena.
+    ///
+    /// The hash distinguishes accounts that don't exist ([`B256::ZERO`]), accounts without code
+    /// ([`KECCAK_EMPTY`](revm::primitives::KECCAK_EMPTY)) and contracts.
+    pub fn ext_code_hashes(&self) -> Vec<HashMap<Address, B256>> {
+        self.nodes()
+            .iter()
+            .map(|node| {
+                node.step_results(opcode::EXTCODEHASH)
+                    .filter_map(|(address, hash)| {
+                        Some((Address::from_word(address?.into()), hash.into()))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the blob index and the resulting versioned hash of all `BLOBHASH` reads, in the
+    /// order of the recorded frames.
+    ///
+    /// Indices that are out of bounds of the transaction's blob hashes read the zero hash.
+    pub fn blob_accesses(&self) -> Vec<(u64, B256)> {
+        self.step_results(opcode::BLOBHASH)
+            .filter_map(|(input, hash)| Some((u64::try_from(input?).ok()?, hash.into())))
+            .collect()
+    }
+
+    /// Returns the blob base fee observed by all `BLOBBASEFEE` reads, in the order of the
+    /// recorded frames.
+    pub fn blob_base_fee_reads(&self) -> Vec<U256> {
+        self.step_results(opcode::BLOBBASEFEE).map(|(_, fee)| fee).collect()
+    }
+
+    /// Returns the top of the stack before and after each step that executed the given opcode.
+    fn step_results(&self, op: u8) -> impl Iterator<Item = (Option<U256>, U256)> + '_ {
+        self.nodes().iter().flat_map(move |node| node.step_results(op))
+    }
+
+    fn validate_node_against_allowlist(
+        &self,
+        node: &CallTraceNode,
+        allowed: &HashSet<u8>,
+    ) -> Result<(), Erc7562Violation> {
+        for item in &node.ordering {
+            match *item {
+                TraceMemberOrder::Step(idx) => {
+                    let opcode = node.trace.steps[idx].op.get();
+                    if !allowed.contains(&opcode) {
+                        return Err(Erc7562Violation {
+                            opcode,
+                            frame: node.idx,
+                            depth: node.trace.depth,
+                            address: node.trace.address,
+                        });
+                    }
+                }
+                TraceMemberOrder::Call(idx) => {
+                    self.validate_node_against_allowlist(&self.arena[node.children[idx]], allowed)?
+                }
+                TraceMemberOrder::Log(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_storage_ops(&self, node: &CallTraceNode, ops: &mut Vec<StorageOp>) {
+        let steps = &node.trace.steps;
+        for item in &node.ordering {
+            match *item {
+                TraceMemberOrder::Step(idx) => {
+                    let step = &steps[idx];
+                    let is_load = match step.op.get() {
+                        opcode::SLOAD | opcode::TLOAD => true,
+                        opcode::SSTORE | opcode::TSTORE => false,
+                        _ => continue,
+                    };
+                    let Some(stack) = step.stack.as_deref() else { continue };
+                    let value = if is_load {
+                        step.push_stack.as_deref().and_then(|pushed| pushed.last())
+                    } else {
+                        stack.iter().nth_back(1)
+                    };
+                    let (Some(slot), Some(value)) = (stack.last(), value) else { continue };
+                    ops.push(StorageOp {
+                        op: step.op,
+                        contract: node.execution_address(),
+                        slot: *slot,
+                        value: *value,
+                        frame: node.idx,
+                        step: idx,
+                    });
+                }
+                TraceMemberOrder::Call(idx) => {
+                    self.collect_storage_ops(&self.arena[node.children[idx]], ops)
+                }
+                TraceMemberOrder::Log(_) => {}
+            }
+        }
+    }
+
+    /// Returns true if a contract calls into itself, directly or indirectly.
+    ///
+    /// See [CallTraceArena::recursion_paths].
+    pub fn has_recursion(&self) -> bool {
+        !self.recursion_paths().is_empty()
+    }
+
+    /// Returns all call paths on which a contract calls into itself, directly or indirectly.
+    ///
+    /// Each path starts with the first call into the contract and ends with the call that enters
+    /// it again, e.g. `[A, B, A]`.
+    ///
+    /// Delegate calls borrow the code of the target but execute in the context of the caller, so
+    /// they are not considered a call into the caller itself.
+    pub fn recursion_paths(&self) -> Vec<Vec<Address>> {
+        let mut paths = Vec::new();
+        for root in self.roots() {
+            self.collect_recursion_paths(root, &mut Vec::new(), &mut paths);
+        }
+        paths
+    }
+
+    fn collect_recursion_paths(
+        &self,
+        node: &CallTraceNode,
+        path: &mut Vec<Address>,
+        paths: &mut Vec<Vec<Address>>,
+    ) {
+        // a delegate call stays in the context of the caller
+        let enters_contract = !node.trace.kind.is_delegate() || path.is_empty();
+        if enters_contract {
+            let address = node.execution_address();
+            if let Some(pos) = path.iter().position(|entered| *entered == address) {
+                let mut recursion = path[pos..].to_vec();
+                recursion.push(address);
+                paths.push(recursion);
+            }
+            path.push(address);
+        }
+
+        for child in &node.children {
+            self.collect_recursion_paths(&self.arena[*child], path, paths);
+        }
+
+        if enters_contract {
+            path.pop();
+        }
+    }
+}
+
+impl CallTraceNode {
+    /// Returns the top of the stack before and after each step of this frame that executed the
+    /// given opcode.
+    fn step_results(&self, op: u8) -> impl Iterator<Item = (Option<U256>, U256)> + '_ {
+        self.trace.steps.iter().filter(move |step| step.op.get() == op).filter_map(|step| {
+            let input = step.stack.as_ref().and_then(|stack| stack.last()).copied();
+            // the result is the value pushed by the step
+            let output = step.push_stack.as_ref()?.last()?;
+            Some((input, *output))
+        })
+    }
+}
@@ -20,7 +20,7 @@
 //! ```
 //!
 //! See also <https://geth.ethereum.org/docs/developers/evm-tracing/built-in-tracers>
-use alloc::format;
+use alloc::{format, vec::Vec};
 use alloy_primitives::{hex, map::HashMap, Selector};
 use alloy_rpc_types_trace::geth::FourByteFrame;
 use revm::{
@@ -41,6 +41,11 @@ impl FourByteInspector {
     pub const fn inner(&self) -> &HashMap<(Selector, usize), u64> {
         &self.inner
     }
+
+    /// Clears all recorded selectors, so the inspector can be reused for the next transaction.
+    pub fn reset(&mut self) {
+        self.inner.clear();
+    }
 }
 
 impl<CTX: ContextTr> Inspector<CTX> for FourByteInspector {
@@ -78,15 +83,76 @@ impl From<FourByteInspector> for FourByteFrame {
 
 impl From<&FourByteInspector> for FourByteFrame {
     fn from(value: &FourByteInspector) -> Self {
-        Self(
-            value
-                .inner
-                .iter()
-                .map(|((selector, calldata_size), count)| {
-                    let key = format!("0x{}-{}", hex::encode(selector), *calldata_size);
-                    (key, *count)
-                })
-                .collect(),
-        )
+        fourbyte_frame(&value.inner)
+    }
+}
+
+/// Block-wide statistics of the function selectors called in a block, merged from the selectors
+/// recorded by a [FourByteInspector] for each transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockSelectorStats {
+    /// The map of SELECTOR to number of occurrences entries across all transactions
+    counts: HashMap<(Selector, usize), u64>,
+}
+
+impl BlockSelectorStats {
+    /// Merges the selectors of all transactions of a block, see [FourByteInspector::inner].
+    pub fn from_transactions<'a>(
+        transactions: impl IntoIterator<Item = &'a HashMap<(Selector, usize), u64>>,
+    ) -> Self {
+        let mut stats = Self::default();
+        for counts in transactions {
+            stats.merge(counts);
+        }
+        stats
+    }
+
+    /// Adds the selectors of a single transaction to the statistics.
+    pub fn merge(&mut self, counts: &HashMap<(Selector, usize), u64>) {
+        for (key, count) in counts {
+            *self.counts.entry(*key).or_default() += count;
+        }
     }
+
+    /// Returns the map of SELECTOR to number of occurrences entries across all transactions
+    pub const fn counts(&self) -> &HashMap<(Selector, usize), u64> {
+        &self.counts
+    }
+
+    /// Returns the total number of recorded calls.
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Returns the `n` most frequent entries, ordered by the number of occurrences.
+    ///
+    /// Entries with the same number of occurrences are ordered by selector and calldata size.
+    pub fn top(&self, n: usize) -> Vec<((Selector, usize), u64)> {
+        let mut entries = self.counts.iter().map(|(key, count)| (*key, *count)).collect::<Vec<_>>();
+        entries.sort_unstable_by(|(a_key, a_count), (b_key, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_key.cmp(b_key))
+        });
+        entries.truncate(n);
+        entries
+    }
+}
+
+impl From<&BlockSelectorStats> for FourByteFrame {
+    fn from(value: &BlockSelectorStats) -> Self {
+        fourbyte_frame(&value.counts)
+    }
+}
+
+/// Converts the map of SELECTOR to number of occurrences entries into a [FourByteFrame] with the
+/// SELECTOR-CALLDATASIZE keys.
+fn fourbyte_frame(counts: &HashMap<(Selector, usize), u64>) -> FourByteFrame {
+    FourByteFrame(
+        counts
+            .iter()
+            .map(|((selector, calldata_size), count)| {
+                let key = format!("0x{}-{}", hex::encode(selector), *calldata_size);
+                (key, *count)
+            })
+            .collect(),
+    )
 }
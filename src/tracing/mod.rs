@@ -3,50 +3,61 @@ use crate::{
     tracing::{
         arena::PushTraceKind,
         types::{
-            CallKind, CallTraceNode, RecordedMemory, StorageChange, StorageChangeReason,
-            TraceMemberOrder,
+            BreakpointSnapshot, CallKind, CallTraceNode, PrecompileInput, RecordedMemory,
+            SourceMap, StorageChange, StorageChangeReason, TraceMemberOrder,
         },
-        utils::gas_used,
+        utils::{gas_used, maybe_custom_error, maybe_revert_reason},
     },
 };
-use alloc::{boxed::Box, vec::Vec};
-use core::{borrow::Borrow, mem};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use alloy_primitives::map::{HashMap, HashSet};
+use core::{borrow::Borrow, fmt, mem};
 use revm::{
     bytecode::opcode::{self, OpCode},
-    context::{JournalTr, LocalContextTr},
+    context::{Block, JournalTr, LocalContextTr, Transaction},
     context_interface::ContextTr,
     inspector::JournalExt,
     interpreter::{
-        interpreter_types::{Immediates, Jumps, LoopControl, ReturnData, RuntimeFlag},
-        CallInput, CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, Interpreter,
-        InterpreterResult,
+        gas,
+        interpreter_types::{Immediates, InputsTr, Jumps, LoopControl, ReturnData, RuntimeFlag},
+        CallInput, CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, CreateScheme,
+        InstructionResult, Interpreter, InterpreterResult,
     },
-    primitives::{hardfork::SpecId, Address, Bytes, Log, B256, U256},
+    primitives::{hardfork::SpecId, keccak256, Address, Bytes, Log, B256, U256},
     Inspector, JournalEntry,
 };
 
 mod arena;
-pub use arena::CallTraceArena;
+pub use arena::{CallTraceArena, DelegateGasAttribution, TraceDiff};
 
 mod builder;
 pub use builder::{
+    block::{self, BlockTraceBuilder},
     geth::{self, GethTraceBuilder},
     parity::{self, ParityTraceBuilder},
 };
 
 mod config;
-pub use config::{OpcodeFilter, StackSnapshotType, TracingInspectorConfig};
+pub use config::{DataCaptureMode, OpcodeFilter, StackSnapshotType, TracingInspectorConfig};
+
+pub mod erc7562;
 
 mod fourbyte;
-pub use fourbyte::FourByteInspector;
+pub use fourbyte::{BlockSelectorStats, FourByteInspector};
 
 mod opcount;
 pub use opcount::OpcodeCountInspector;
 
+#[cfg(feature = "profiling")]
+mod profile;
+#[cfg(feature = "profiling")]
+pub use profile::InspectorProfile;
+
 pub mod types;
 use types::{CallLog, CallTrace, CallTraceStep};
 
 mod utils;
+pub use utils::predict_create2_address;
 
 #[cfg(feature = "std")]
 mod writer;
@@ -75,6 +86,10 @@ pub struct TracingInspector {
     traces: CallTraceArena,
     /// Tracks active calls
     trace_stack: Vec<usize>,
+    /// Number of currently active calls that are not recorded.
+    ///
+    /// Everything that happens inside a skipped call is not recorded either.
+    skipped_calls: usize,
     /// Tracks whether the next `step_end` should be recorded. Set in `start_step`.
     record_step_end: bool,
     /// Tracks the return value of the last call
@@ -89,6 +104,83 @@ pub struct TracingInspector {
     ///
     /// All `Vec<CallTraceStep>` are always empty but may have capacity.
     reusable_step_vecs: Vec<Vec<CallTraceStep>>,
+    /// Callback invoked with each recorded step, see [TracingInspector::on_step].
+    step_hook: Option<StepHook>,
+    /// Callback invoked with each recorded call frame, see [TracingInspector::on_frame].
+    frame_hook: Option<FrameHook>,
+    /// Source maps of the code of contracts, see [TracingInspector::with_source_map].
+    source_maps: HashMap<Address, SourceMap>,
+    /// Gas costs of opcodes that replace the measured costs of the recorded steps, see
+    /// [TracingInspector::with_gas_cost_overrides].
+    gas_cost_overrides: HashMap<u8, u64>,
+    /// The intrinsic gas of the transaction.
+    ///
+    /// This is filled when the root call starts.
+    intrinsic_gas: u64,
+    /// The fees paid by the transaction.
+    ///
+    /// This is filled when the root call starts.
+    fees: Option<TransactionFees>,
+    /// The last opcode executed inside a static call.
+    ///
+    /// A failed call takes this as its [CallTrace::static_call_violation].
+    last_static_op: Option<u8>,
+    /// The memory region of the arguments of the last call opcode, as offset and size.
+    ///
+    /// The next call takes this as its [CallTrace::args_offset] and [CallTrace::args_size].
+    last_call_args: Option<(usize, usize)>,
+    /// The gas requested by the call opcode that is currently executed.
+    ///
+    /// This is completed in `step_end` and moved to [TracingInspector::last_call_gas].
+    pending_call_gas: Option<u64>,
+    /// The gas requested by the last call opcode and the gas remaining in the caller after it,
+    /// which no longer includes the forwarded gas.
+    ///
+    /// The next call takes this as its [CallTrace::requested_gas] and [CallTrace::caller_gas].
+    last_call_gas: Option<(u64, u64)>,
+    /// Whether the last `SELFDESTRUCT` deletes the contract.
+    ///
+    /// The next selfdestruct takes this as its [CallTrace::selfdestruct_destroyed].
+    last_selfdestruct_destroys: Option<bool>,
+    /// The `(code_address, pc)` pairs to take a snapshot at, see
+    /// [TracingInspector::with_breakpoints].
+    breakpoints: HashSet<(Address, u64)>,
+    /// The snapshots taken at the breakpoints.
+    breakpoint_hits: Vec<BreakpointSnapshot>,
+    /// Whether recording was interrupted, see [TracingInspector::set_interrupt].
+    interrupted: bool,
+    /// Whether calls were not recorded because the
+    /// [max_frames](TracingInspectorConfig::max_frames) were reached.
+    frames_truncated: bool,
+    /// The time spent inside the callbacks, see [TracingInspector::profiling_stats].
+    #[cfg(feature = "profiling")]
+    profile: InspectorProfile,
+}
+
+/// A callback invoked with each recorded step.
+type StepHookFn = dyn Fn(&CallTraceStep, &Interpreter) + Send + Sync;
+
+/// A registered [StepHookFn].
+#[derive(Clone)]
+struct StepHook(Arc<StepHookFn>);
+
+impl fmt::Debug for StepHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StepHook").finish_non_exhaustive()
+    }
+}
+
+/// A callback returning the metadata of each recorded call frame.
+type FrameHookFn = dyn Fn(&CallTraceNode) -> Option<serde_json::Value> + Send + Sync;
+
+/// A registered [FrameHookFn].
+#[derive(Clone)]
+struct FrameHook(Arc<FrameHookFn>);
+
+impl fmt::Debug for FrameHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FrameHook").finish_non_exhaustive()
+    }
 }
 
 impl TracingInspector {
@@ -106,13 +198,31 @@ impl TracingInspector {
         let Self {
             traces,
             trace_stack,
+            skipped_calls,
             last_call_return_data,
             last_journal_len,
             spec_id,
             record_step_end,
+            intrinsic_gas,
+            fees,
+            last_static_op,
+            last_call_args,
+            pending_call_gas,
+            last_call_gas,
+            last_selfdestruct_destroys,
+            breakpoint_hits,
+            interrupted,
+            frames_truncated,
+            #[cfg(feature = "profiling")]
+            profile,
             // kept
             config,
             reusable_step_vecs,
+            step_hook: _,
+            frame_hook: _,
+            source_maps: _,
+            gas_cost_overrides: _,
+            breakpoints: _,
         } = self;
 
         // if we record steps we can reuse the individual calltracestep vecs
@@ -128,10 +238,25 @@ impl TracingInspector {
 
         traces.clear();
         trace_stack.clear();
+        *skipped_calls = 0;
         last_call_return_data.take();
         spec_id.take();
         *last_journal_len = 0;
         *record_step_end = false;
+        *intrinsic_gas = 0;
+        fees.take();
+        last_static_op.take();
+        last_call_args.take();
+        pending_call_gas.take();
+        last_call_gas.take();
+        last_selfdestruct_destroys.take();
+        breakpoint_hits.clear();
+        *interrupted = false;
+        *frames_truncated = false;
+        #[cfg(feature = "profiling")]
+        {
+            *profile = InspectorProfile::default();
+        }
     }
 
     /// Resets the inspector to it's initial state of [Self::new].
@@ -159,6 +284,96 @@ impl TracingInspector {
         self.config = f(self.config);
     }
 
+    /// Registers a callback that is invoked with each recorded step and the interpreter after the
+    /// step was executed.
+    ///
+    /// The callback is invoked once the step is complete, in [Inspector::step_end], so all
+    /// recorded fields of the step are already filled. This only sees the steps that are
+    /// recorded according to the [TracingInspectorConfig]. The callback is kept when the
+    /// inspector is [fused](Self::fuse) and replaces any previously registered callback.
+    pub fn on_step(&mut self, f: impl Fn(&CallTraceStep, &Interpreter) + Send + Sync + 'static) {
+        self.step_hook = Some(StepHook(Arc::new(f)));
+    }
+
+    /// Registers a callback that is invoked with each recorded call frame once it's complete and
+    /// returns the [metadata](CallTraceNode::metadata) to attach to the frame.
+    ///
+    /// The frame is passed once its subcalls and logs are recorded. The callback is kept when the
+    /// inspector is [fused](Self::fuse) and replaces any previously registered callback.
+    pub fn on_frame(
+        &mut self,
+        f: impl Fn(&CallTraceNode) -> Option<serde_json::Value> + Send + Sync + 'static,
+    ) {
+        self.frame_hook = Some(FrameHook(Arc::new(f)));
+    }
+
+    /// Sets the source maps of the code of contracts, keyed by the address of the code.
+    ///
+    /// Each recorded step of code with a source map is annotated with the
+    /// [source location](CallTraceStep::source_location) of its program counter.
+    pub fn with_source_map(mut self, source_maps: HashMap<Address, SourceMap>) -> Self {
+        self.source_maps = source_maps;
+        self
+    }
+
+    /// Sets the gas costs of opcodes, e.g. of a chain that reprices some opcodes, keyed by the
+    /// opcode byte.
+    ///
+    /// The [gas cost](CallTraceStep::gas_cost) of each recorded step with an overridden opcode is
+    /// set to the given cost instead of the gas measured during execution. Steps of all other
+    /// opcodes keep the measured cost.
+    pub fn with_gas_cost_overrides(mut self, gas_costs: HashMap<u8, u64>) -> Self {
+        self.gas_cost_overrides = gas_costs;
+        self
+    }
+
+    /// Sets the breakpoints to take a [BreakpointSnapshot] at, as pairs of the address of the code
+    /// and the program counter.
+    ///
+    /// The stack and memory are captured whenever the opcode at one of the program counters is
+    /// about to be executed, regardless of whether steps are recorded. Breakpoints inside calls
+    /// that are not recorded, e.g. with [TracingInspectorConfig::only_top_call] or after an
+    /// interrupt, are not captured. For init code the address is the address of the created
+    /// contract.
+    pub fn with_breakpoints(mut self, breakpoints: HashSet<(Address, u64)>) -> Self {
+        self.breakpoints = breakpoints;
+        self
+    }
+
+    /// Returns the snapshots taken at the [breakpoints](Self::with_breakpoints) in execution
+    /// order.
+    pub fn breakpoint_hits(&self) -> &[BreakpointSnapshot] {
+        &self.breakpoint_hits
+    }
+
+    /// Stops recording the remainder of the execution, e.g. once a consumer found what it needs.
+    ///
+    /// Calls, steps and logs that start after this are not recorded, so the remaining execution
+    /// has minimal overhead. The calls that are active when this is set are still completed, so
+    /// the recorded traces stay consistent. The interrupt is cleared when the inspector is
+    /// [fused](Self::fuse).
+    pub fn set_interrupt(&mut self) {
+        self.interrupted = true;
+    }
+
+    /// Returns true if recording was interrupted, see [Self::set_interrupt].
+    pub const fn is_interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// Returns true if calls were not recorded because the configured
+    /// [max_frames](TracingInspectorConfig::max_frames) were reached.
+    pub const fn frames_truncated(&self) -> bool {
+        self.frames_truncated
+    }
+
+    /// Returns the wall-clock time spent inside the callbacks of this inspector since it was
+    /// created or last [fused](Self::fuse).
+    #[cfg(feature = "profiling")]
+    pub const fn profiling_stats(&self) -> InspectorProfile {
+        self.profile
+    }
+
     /// Gets a reference to the recorded call traces.
     pub const fn traces(&self) -> &CallTraceArena {
         &self.traces
@@ -186,6 +401,25 @@ impl TracingInspector {
         self.traces
     }
 
+    /// Returns the gas used by the whole transaction as seen by the tracer.
+    ///
+    /// This is the intrinsic gas of the transaction plus the gas used by the root call, which can
+    /// be cross-checked against the `gasUsed` of the receipt. Unlike the receipt, this does not
+    /// deduct the [refund](CallTraceArena::total_refund) and is not raised to the calldata floor
+    /// cost of [EIP-7623](https://eips.ethereum.org/EIPS/eip-7623), so they only match if neither
+    /// applies.
+    pub fn total_gas_used(&self) -> u64 {
+        let gas_used =
+            self.traces.arena.first().map(|node| node.trace.gas_used).unwrap_or_default();
+        self.intrinsic_gas + gas_used
+    }
+
+    /// Returns the fees paid per gas by the transaction, or `None` if no transaction was traced
+    /// yet.
+    pub const fn transaction_fees(&self) -> Option<TransactionFees> {
+        self.fees
+    }
+
     /// Manually set the gas used of the root trace.
     ///
     /// This is useful if the root trace's gasUsed should mirror the actual gas used by the
@@ -212,6 +446,22 @@ impl TracingInspector {
         }
     }
 
+    /// Sets the [mint](CallTrace::mint) and [source hash](CallTrace::source_hash) of the root
+    /// trace if the given transaction is an OP-stack deposit transaction.
+    ///
+    /// The inspector only sees the transaction through the generic [Transaction] interface, so this
+    /// has to be called with the traced transaction once it was executed.
+    #[cfg(feature = "optimism")]
+    pub fn set_deposit_transaction(&mut self, tx: &impl op_revm::transaction::OpTxTr) {
+        if !tx.is_deposit() {
+            return;
+        }
+        if let Some(node) = self.traces.arena.first_mut() {
+            node.trace.mint = tx.mint().map(U256::from);
+            node.trace.source_hash = tx.source_hash();
+        }
+    }
+
     /// Convenience function for [ParityTraceBuilder::set_transaction_gas_used] that consumes the
     /// type.
     #[inline]
@@ -255,6 +505,44 @@ impl TracingInspector {
         !self.trace_stack.is_empty()
     }
 
+    /// Returns true if we're inside a call that is not recorded.
+    #[inline]
+    const fn is_skipping_call(&self) -> bool {
+        self.skipped_calls != 0
+    }
+
+    /// Returns true if the call that is about to start should not be recorded.
+    ///
+    /// If so, the call is tracked as skipped until the matching [Self::end_skipped_call].
+    #[inline]
+    fn start_skipped_call(&mut self) -> bool {
+        if self.is_skipping_call()
+            || self.interrupted
+            || (self.config.only_top_call && self.is_deep())
+        {
+            self.skipped_calls += 1;
+            return true;
+        }
+        if self.is_deep()
+            && self.config.max_frames.is_some_and(|max| self.traces.nodes().len() >= max)
+        {
+            self.frames_truncated = true;
+            self.skipped_calls += 1;
+            return true;
+        }
+        false
+    }
+
+    /// Returns true if the call that just ended was not recorded.
+    #[inline]
+    fn end_skipped_call(&mut self) -> bool {
+        if self.is_skipping_call() {
+            self.skipped_calls -= 1;
+            return true;
+        }
+        false
+    }
+
     /// Returns how many logs we already recorded.
     fn log_count(&self) -> usize {
         self.traces.nodes().iter().map(|trace| trace.log_count()).sum()
@@ -341,17 +629,30 @@ impl TracingInspector {
             PushTraceKind::PushAndAttachToParent
         };
 
+        let depth = context.journal().depth();
+        if depth == 0 {
+            // the root call is started with the gas left after the intrinsic gas was deducted
+            self.intrinsic_gas = context.tx().gas_limit().saturating_sub(gas_limit);
+            self.fees = Some(TransactionFees::new(context));
+        }
+
         // find an empty steps vec or create a new one
         let steps = self.reusable_step_vecs.pop().unwrap_or_default();
 
+        let input_size = input_data.len() as u64;
+        let input_hash = self.config.record_data_hashes.then(|| keccak256(&input_data));
+        let input_data = if self.config.data_capture.is_full() { input_data } else { Bytes::new() };
+
         self.trace_stack.push(self.traces.push_trace(
             0,
             push_kind,
             CallTrace {
-                depth: context.journal().depth(),
+                depth,
                 address,
                 kind,
                 data: input_data,
+                input_size,
+                input_hash,
                 value,
                 status: None,
                 caller,
@@ -381,17 +682,98 @@ impl TracingInspector {
         let trace = &mut self.traces.arena[trace_idx].trace;
 
         trace.gas_used = gas.spent();
+        // the refund counter of a single call can be negative, e.g. if a slot is restored
+        trace.refund = gas.refunded().max(0) as u64;
 
         trace.status = Some(result);
+        // a call without code stops right away, any other result means that the call failed
+        // before its code was loaded, e.g. because the call stack is too deep
+        trace.is_empty_code &= result == InstructionResult::Stop;
+        if matches!(
+            result,
+            InstructionResult::StateChangeDuringStaticCall
+                | InstructionResult::CallNotAllowedInsideStatic
+        ) {
+            trace.static_call_violation = self.last_static_op.take().and_then(OpCode::new);
+        }
         trace.success = trace.status.is_some_and(|status| status.is_ok());
-        trace.output = output.clone();
+        trace.output_size = output.len() as u64;
+        if self.config.record_data_hashes {
+            trace.output_hash = Some(keccak256(output));
+        }
+        if self.config.data_capture.is_full() {
+            trace.output = output.clone();
+        }
 
         self.last_call_return_data = Some(output.clone());
 
         if let Some(address) = created_address {
-            // A new contract was created via CREATE
+            // A new contract was created via CREATE, usually at the address predicted when it
+            // started, unless another inspector overrode the outcome
             trace.address = address;
         }
+
+        if trace.is_revert() {
+            trace.custom_error = maybe_custom_error(output);
+        }
+
+        if trace.is_revert() && output.is_empty() {
+            let reason = self
+                .traces
+                .revert_origin(trace_idx)
+                .and_then(|origin| maybe_revert_reason(&origin.trace.output));
+            self.traces.arena[trace_idx].trace.forwarded_revert_reason = reason;
+        }
+
+        if let Some(FrameHook(hook)) = &self.frame_hook {
+            let node = &mut self.traces.arena[trace_idx];
+            node.metadata = hook(node);
+        }
+    }
+
+    /// Records the memory region of the arguments of a call opcode, given the stack position of
+    /// the arguments offset.
+    #[inline]
+    fn record_call_args(&mut self, interp: &Interpreter, args: usize) {
+        self.last_call_args = match (interp.stack.peek(args), interp.stack.peek(args + 1)) {
+            (Ok(offset), Ok(size)) => Some((offset.saturating_to(), size.saturating_to())),
+            _ => None,
+        };
+    }
+
+    /// Takes a [BreakpointSnapshot] if the step that is about to be executed is at a breakpoint.
+    fn record_breakpoint_hit(&mut self, interp: &mut Interpreter) {
+        let address = interp.input.target_address();
+        let code_address = interp.input.bytecode_address().copied().unwrap_or(address);
+        let pc = interp.bytecode.pc() as u64;
+        if !self.breakpoints.contains(&(code_address, pc)) {
+            return;
+        }
+        self.breakpoint_hits.push(BreakpointSnapshot {
+            code_address,
+            pc,
+            address,
+            frame: self.trace_stack.last().copied().unwrap_or_default(),
+            stack: interp.stack.data().clone(),
+            memory: RecordedMemory::new(&interp.memory.borrow().context_memory()),
+            gas_remaining: interp.gas.remaining(),
+        });
+    }
+
+    /// Records whether the `SELFDESTRUCT` that is about to be executed deletes the contract.
+    fn record_selfdestruct_destroys<CTX: ContextTr<Journal: JournalExt>>(
+        &mut self,
+        interp: &Interpreter,
+        context: &CTX,
+    ) {
+        // since Cancun only contracts created in the same transaction are deleted
+        let created = context
+            .journal_ref()
+            .evm_state()
+            .get(&interp.input.target_address())
+            .is_some_and(|account| account.is_created_locally());
+        self.last_selfdestruct_destroys =
+            Some(created || !interp.runtime_flag.spec_id().is_enabled_in(SpecId::CANCUN));
     }
 
     /// Starts tracking a step
@@ -468,9 +850,16 @@ impl TracingInspector {
 
         self.last_journal_len = context.journal_ref().journal().len();
 
+        let pc = interp.bytecode.pc();
+        let source_location = self
+            .source_maps
+            .get(&node.trace.address)
+            .and_then(|source_map| source_map.get(&pc))
+            .map(|location| Box::new(location.clone()));
+
         let step_idx = node.trace.steps.len();
         node.trace.steps.push(CallTraceStep {
-            pc: interp.bytecode.pc(),
+            pc,
             op,
             stack,
             memory,
@@ -479,6 +868,7 @@ impl TracingInspector {
             gas_refund_counter: interp.gas.refunded() as u64,
             gas_used,
             immediate_bytes,
+            source_location,
 
             // These fields will be populated in `step_end`.
             push_stack: None,
@@ -507,7 +897,20 @@ impl TracingInspector {
         if !self.record_step_end {
             return;
         }
+        self.fill_step(interp, context);
+
+        if let Some(StepHook(hook)) = &self.step_hook {
+            let step = self.traces.arena[self.last_trace_idx()].trace.steps.last().unwrap();
+            hook(step, interp);
+        }
+    }
 
+    /// Fills the last recorded step with the output of the step.
+    fn fill_step<CTX: ContextTr<Journal: JournalExt>>(
+        &mut self,
+        interp: &mut Interpreter,
+        context: &mut CTX,
+    ) {
         let trace_idx = self.last_trace_idx();
         let node = &mut self.traces.arena[trace_idx];
         let step = node.trace.steps.last_mut().unwrap();
@@ -525,8 +928,10 @@ impl TracingInspector {
         if self.config.record_stack_snapshots.is_all()
             || self.config.record_stack_snapshots.is_pushes()
         {
+            // undefined opcodes don't push anything
+            let outputs = OpCode::info_by_op(step.op.get()).map_or(0, |info| info.outputs());
             // this can potentially underflow if the stack is malformed
-            let start = interp.stack.len().saturating_sub(step.op.outputs() as usize);
+            let start = interp.stack.len().saturating_sub(outputs as usize);
             step.push_stack = Some(interp.stack.data()[start..].into());
         }
 
@@ -560,48 +965,87 @@ impl TracingInspector {
         }
 
         // The gas cost is the difference between the recorded gas remaining at the start of the
-        // step the remaining gas here, at the end of the step.
+        // step the remaining gas here, at the end of the step, unless the opcode is overridden.
         // TODO: Figure out why this can overflow. https://github.com/paradigmxyz/revm-inspectors/pull/38
-        step.gas_cost = step.gas_remaining.saturating_sub(interp.gas.remaining());
+        step.gas_cost = match self.gas_cost_overrides.get(&step.op.get()) {
+            Some(gas_cost) => *gas_cost,
+            None => step.gas_remaining.saturating_sub(interp.gas.remaining()),
+        };
 
         // set the status
         step.status = interp.bytecode.action().as_ref().and_then(|i| i.instruction_result())
     }
-}
 
-impl<CTX> Inspector<CTX> for TracingInspector
-where
-    CTX: ContextTr<Journal: JournalExt>,
-{
-    #[inline]
-    fn step(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+    /// Records the step that is about to be executed, see [Inspector::step].
+    fn trace_step<CTX: ContextTr<Journal: JournalExt>>(
+        &mut self,
+        interp: &mut Interpreter,
+        context: &mut CTX,
+    ) {
+        if self.interrupted {
+            // a step that started before the interrupt is still completed in `step_end`
+            self.record_step_end = false;
+            return;
+        }
+        if self.is_skipping_call() {
+            return;
+        }
+
+        let op = interp.bytecode.opcode();
+        if interp.runtime_flag.is_static() {
+            self.last_static_op = Some(op);
+        }
+        match op {
+            opcode::CALL | opcode::CALLCODE => {
+                self.record_call_args(interp, 3);
+                self.pending_call_gas = interp.stack.peek(0).ok().map(|gas| gas.saturating_to());
+            }
+            opcode::DELEGATECALL | opcode::STATICCALL => {
+                self.record_call_args(interp, 2);
+                self.pending_call_gas = interp.stack.peek(0).ok().map(|gas| gas.saturating_to());
+            }
+            opcode::SELFDESTRUCT => self.record_selfdestruct_destroys(interp, context),
+            _ => {}
+        }
+
+        if !self.breakpoints.is_empty() {
+            self.record_breakpoint_hit(interp);
+        }
+
+        // nothing else of the step is needed if only calls are recorded
         if self.config.record_steps {
             self.start_step(interp, context);
         }
     }
 
-    #[inline]
-    fn step_end(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+    /// Completes the step that was just executed, see [Inspector::step_end].
+    fn trace_step_end<CTX: ContextTr<Journal: JournalExt>>(
+        &mut self,
+        interp: &mut Interpreter,
+        context: &mut CTX,
+    ) {
+        if self.is_skipping_call() {
+            return;
+        }
+        // the call opcode already deducted the gas it forwards
+        if let Some(requested) = self.pending_call_gas.take() {
+            self.last_call_gas = Some((requested, interp.gas.remaining()));
+        }
         if self.config.record_steps {
             self.fill_step_on_step_end(interp, context);
         }
     }
 
-    fn log(&mut self, _interp: &mut Interpreter, _context: &mut CTX, log: Log) {
-        if self.config.record_logs {
-            // index starts at 0
-            let log_count = self.log_count();
-            let trace = self.last_trace();
-            trace.ordering.push(TraceMemberOrder::Log(trace.logs.len()));
-            trace.logs.push(
-                CallLog::from(log)
-                    .with_position(trace.children.len() as u64)
-                    .with_index(log_count as u64),
-            );
+    /// Starts recording a call, see [Inspector::call].
+    fn trace_call<CTX: ContextTr<Journal: JournalExt>>(
+        &mut self,
+        context: &mut CTX,
+        inputs: &CallInputs,
+    ) {
+        if self.start_skipped_call() {
+            return;
         }
-    }
 
-    fn call(&mut self, context: &mut CTX, inputs: &mut CallInputs) -> Option<CallOutcome> {
         // determine correct `from` and `to` based on the call scheme
         let (from, to) = match inputs.scheme {
             CallScheme::DelegateCall | CallScheme::CallCode => {
@@ -627,7 +1071,16 @@ where
             .exclude_precompile_calls
             .then(|| self.is_precompile_call(context, &to, &value));
 
+        // the root call is not made by a call opcode
+        let args = if self.is_deep() { self.last_call_args.take() } else { None };
+        let call_gas = if self.is_deep() { self.last_call_gas.take() } else { None };
+
         let input = inputs.input_data(context);
+        let precompile_input = if self.config.record_precompile_inputs {
+            PrecompileInput::decode(to, &input)
+        } else {
+            None
+        };
         self.start_trace_on_call(
             context,
             to,
@@ -638,19 +1091,55 @@ where
             inputs.gas_limit,
             maybe_precompile,
         );
-
-        None
+        let is_precompile = context.journal_ref().precompile_addresses().contains(&to);
+        let trace = &mut self.last_trace().trace;
+        trace.is_precompile = is_precompile;
+        // cleared once the code of the call starts executing, see [Inspector::initialize_interp]
+        trace.is_empty_code = !is_precompile;
+        trace.precompile_input = precompile_input.filter(|_| is_precompile);
+        trace.args_offset = args.map(|(offset, _)| offset);
+        trace.args_size = args.map(|(_, size)| size);
+        if let Some((requested, remaining)) = call_gas {
+            // the stipend of a value transfer is given on top of the forwarded gas
+            let stipend = if inputs.transfers_value() { gas::CALL_STIPEND } else { 0 };
+            trace.requested_gas = Some(requested);
+            trace.caller_gas = Some(remaining + inputs.gas_limit.saturating_sub(stipend));
+        }
     }
 
-    fn call_end(&mut self, _: &mut CTX, _inputs: &CallInputs, outcome: &mut CallOutcome) {
+    /// Completes the recording of a call, see [Inspector::call_end].
+    fn trace_call_end(&mut self, outcome: &CallOutcome) {
+        if self.end_skipped_call() {
+            return;
+        }
         self.fill_trace_on_call_end(&outcome.result, None);
     }
 
-    fn create(&mut self, context: &mut CTX, inputs: &mut CreateInputs) -> Option<CreateOutcome> {
-        let nonce = context.journal_mut().load_account(inputs.caller).ok()?.info.nonce;
+    /// Starts recording a contract creation, see [Inspector::create].
+    fn trace_create<CTX: ContextTr<Journal: JournalExt>>(
+        &mut self,
+        context: &mut CTX,
+        inputs: &CreateInputs,
+    ) {
+        if self.start_skipped_call() {
+            return;
+        }
+        let Ok(caller) = context.journal_mut().load_account(inputs.caller) else { return };
+        let nonce = caller.info.nonce;
+        // the address is known before the creation is executed
+        let create2 = match inputs.scheme {
+            CreateScheme::Create2 { salt } => Some((salt, keccak256(&inputs.init_code))),
+            _ => None,
+        };
+        let address = match create2 {
+            Some((salt, init_code_hash)) => {
+                predict_create2_address(inputs.caller, salt, init_code_hash)
+            }
+            None => inputs.created_address(nonce),
+        };
         self.start_trace_on_call(
             context,
-            inputs.created_address(nonce),
+            address,
             inputs.init_code.clone(),
             inputs.value,
             inputs.scheme.into(),
@@ -658,6 +1147,104 @@ where
             inputs.gas_limit,
             Some(false),
         );
+        let capture_data = self.config.data_capture.is_full();
+        let trace = &mut self.last_trace().trace;
+        if capture_data {
+            trace.init_code = Some(inputs.init_code.clone());
+        }
+        if let Some((salt, init_code_hash)) = create2 {
+            trace.create2_salt = Some(salt);
+            trace.init_code_hash = Some(init_code_hash);
+        }
+    }
+
+    /// Completes the recording of a contract creation, see [Inspector::create_end].
+    fn trace_create_end(&mut self, outcome: &CreateOutcome) {
+        if self.end_skipped_call() {
+            return;
+        }
+        self.fill_trace_on_call_end(&outcome.result, outcome.address);
+    }
+}
+
+impl<CTX> Inspector<CTX> for TracingInspector
+where
+    CTX: ContextTr<Journal: JournalExt>,
+{
+    #[inline]
+    fn initialize_interp(&mut self, _interp: &mut Interpreter, _context: &mut CTX) {
+        // the interpreter is only created for calls with code
+        if !self.is_skipping_call() && !self.interrupted {
+            self.last_trace().trace.is_empty_code = false;
+        }
+    }
+
+    #[inline]
+    fn step(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+        #[cfg(feature = "profiling")]
+        let start = std::time::Instant::now();
+        self.trace_step(interp, context);
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.total_step_time += start.elapsed();
+            self.profile.step_count += 1;
+        }
+    }
+
+    #[inline]
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+        #[cfg(feature = "profiling")]
+        let start = std::time::Instant::now();
+        self.trace_step_end(interp, context);
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.total_step_time += start.elapsed();
+        }
+    }
+
+    fn log(&mut self, _interp: &mut Interpreter, _context: &mut CTX, log: Log) {
+        if self.config.record_logs && !self.is_skipping_call() && !self.interrupted {
+            // index starts at 0
+            let log_count = self.log_count();
+            let trace = self.last_trace();
+            trace.ordering.push(TraceMemberOrder::Log(trace.logs.len()));
+            trace.logs.push(
+                CallLog::from(log)
+                    .with_position(trace.children.len() as u64)
+                    .with_index(log_count as u64),
+            );
+        }
+    }
+
+    fn call(&mut self, context: &mut CTX, inputs: &mut CallInputs) -> Option<CallOutcome> {
+        #[cfg(feature = "profiling")]
+        let start = std::time::Instant::now();
+        self.trace_call(context, inputs);
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.total_call_time += start.elapsed();
+        }
+        None
+    }
+
+    fn call_end(&mut self, _: &mut CTX, _inputs: &CallInputs, outcome: &mut CallOutcome) {
+        #[cfg(feature = "profiling")]
+        let start = std::time::Instant::now();
+        self.trace_call_end(outcome);
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.total_call_time += start.elapsed();
+        }
+    }
+
+    fn create(&mut self, context: &mut CTX, inputs: &mut CreateInputs) -> Option<CreateOutcome> {
+        #[cfg(feature = "profiling")]
+        let start = std::time::Instant::now();
+        self.trace_create(context, inputs);
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.total_call_time += start.elapsed();
+        }
         None
     }
 
@@ -667,14 +1254,59 @@ where
         _inputs: &CreateInputs,
         outcome: &mut CreateOutcome,
     ) {
-        self.fill_trace_on_call_end(&outcome.result, outcome.address);
+        #[cfg(feature = "profiling")]
+        let start = std::time::Instant::now();
+        self.trace_create_end(outcome);
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.total_call_time += start.elapsed();
+        }
     }
 
     fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        if self.is_skipping_call() || self.interrupted {
+            return;
+        }
+        let destroyed = self.last_selfdestruct_destroys.take();
         let node = self.last_trace();
         node.trace.selfdestruct_address = Some(contract);
         node.trace.selfdestruct_refund_target = Some(target);
         node.trace.selfdestruct_transferred_value = Some(value);
+        node.trace.selfdestruct_destroyed = destroyed;
+    }
+}
+
+/// The fees paid per gas by a transaction, see [TracingInspector::transaction_fees].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransactionFees {
+    /// The gas price paid by the transaction.
+    ///
+    /// This is the gas price of legacy and EIP-2930 transactions. Since EIP-1559 this is the
+    /// minimum of the max fee per gas and the base fee plus the max priority fee per gas.
+    pub effective_gas_price: u128,
+    /// The base fee of the block.
+    pub base_fee: u64,
+    /// The part of the [`Self::effective_gas_price`] above the base fee, which is paid to the
+    /// block's beneficiary.
+    pub priority_fee: u128,
+    /// The blob gas price of the block, only set for EIP-4844 transactions.
+    pub blob_gas_price: Option<u128>,
+}
+
+impl TransactionFees {
+    /// Returns the fees of the transaction of the given context.
+    pub fn new<CTX: ContextTr>(context: &CTX) -> Self {
+        let base_fee = context.block().basefee();
+        let effective_gas_price = context.tx().effective_gas_price(base_fee as u128);
+        let blob_gas_price = (!context.tx().blob_versioned_hashes().is_empty())
+            .then(|| context.block().blob_gasprice())
+            .flatten();
+        Self {
+            effective_gas_price,
+            base_fee,
+            priority_fee: effective_gas_price.saturating_sub(base_fee as u128),
+            blob_gas_price,
+        }
     }
 }
 
@@ -1,11 +1,12 @@
 use crate::tracing::{FourByteInspector, TracingInspector, TracingInspectorConfig};
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 use alloy_primitives::{map::HashMap, Address, Log, U256};
 use alloy_rpc_types_eth::TransactionInfo;
 use alloy_rpc_types_trace::geth::{
+    erc7562::Erc7562Config,
     mux::{MuxConfig, MuxFrame},
-    CallConfig, FlatCallConfig, FourByteFrame, GethDebugBuiltInTracerType, NoopFrame,
-    PreStateConfig,
+    CallConfig, FlatCallConfig, FourByteFrame, GethDebugBuiltInTracerType, GethDebugTracerConfig,
+    GethTrace, NoopFrame, PreStateConfig,
 };
 use revm::{
     context_interface::{
@@ -27,6 +28,10 @@ pub struct MuxInspector {
     tracing: Option<TracingInspector>,
     /// Configurations for different Geth trace types
     configs: Vec<(GethDebugBuiltInTracerType, TraceConfig)>,
+    /// The single tracer this inspector was created for, if not created from a [MuxConfig].
+    ///
+    /// See [MuxInspector::try_from_tracer_name].
+    tracer: Option<GethDebugBuiltInTracerType>,
 }
 
 /// Holds all Geth supported trace configurations
@@ -35,6 +40,7 @@ enum TraceConfig {
     Call(CallConfig),
     PreState(PreStateConfig),
     FlatCall(FlatCallConfig),
+    Erc7562(Erc7562Config),
     Noop,
 }
 
@@ -87,6 +93,14 @@ impl MuxInspector {
                         .merge(TracingInspectorConfig::from_flat_call_config(&flatcall_config));
                     configs.push((tracer_type, TraceConfig::FlatCall(flatcall_config)));
                 }
+                GethDebugBuiltInTracerType::Erc7562Tracer => {
+                    let erc7562_config: Erc7562Config =
+                        tracer_config.ok_or(Error::MissingConfig(tracer_type))?.from_value()?;
+
+                    inspector_config
+                        .merge(TracingInspectorConfig::from_geth_erc7562_config(&erc7562_config));
+                    configs.push((tracer_type, TraceConfig::Erc7562(erc7562_config)));
+                }
                 GethDebugBuiltInTracerType::MuxTracer => {
                     return Err(Error::UnexpectedConfig(tracer_type));
                 }
@@ -95,7 +109,64 @@ impl MuxInspector {
 
         let tracing = (!configs.is_empty()).then(|| TracingInspector::new(inspector_config));
 
-        Ok(MuxInspector { four_byte, tracing, configs })
+        Ok(MuxInspector { four_byte, tracing, configs, tracer: None })
+    }
+
+    /// Resets the inspector to its initial state, so it can be used for the next transaction.
+    ///
+    /// See [TracingInspector::fuse].
+    pub fn fuse(&mut self) {
+        if let Some(four_byte) = &mut self.four_byte {
+            four_byte.reset();
+        }
+        if let Some(tracing) = &mut self.tracing {
+            tracing.fuse();
+        }
+    }
+
+    /// Try creating a new instance of [MuxInspector] for the built-in geth tracer with the given
+    /// name, e.g. `callTracer`, and its JSON config.
+    ///
+    /// This is the entry point for `debug_traceCall` and `debug_traceTransaction`: the
+    /// [GethTrace] of the requested tracer can be obtained via
+    /// [MuxInspector::try_into_geth_trace].
+    pub fn try_from_tracer_name(
+        name: &str,
+        config: GethDebugTracerConfig,
+    ) -> Result<MuxInspector, Error> {
+        let tracer_type: GethDebugBuiltInTracerType =
+            serde_json::from_value(serde_json::Value::String(name.into()))
+                .map_err(|_| Error::UnknownTracer(name.into()))?;
+
+        let tracer_config = match tracer_type {
+            GethDebugBuiltInTracerType::MuxTracer => {
+                return Self::try_from_config(config.into_mux_config()?);
+            }
+            GethDebugBuiltInTracerType::FourByteTracer | GethDebugBuiltInTracerType::NoopTracer => {
+                (!config.is_null()).then_some(config)
+            }
+            _ => Some(config),
+        };
+
+        let mut inspector =
+            Self::try_from_config(MuxConfig(HashMap::from_iter([(tracer_type, tracer_config)])))?;
+        inspector.tracer = Some(tracer_type);
+        Ok(inspector)
+    }
+
+    /// Try converting this [MuxInspector] into the [GethTrace] of the configured tracer.
+    ///
+    /// If this inspector was created from a [MuxConfig], this returns the
+    /// [GethTrace::MuxTracer] frame.
+    pub fn try_into_geth_trace<DB: DatabaseRef>(
+        &self,
+        result: &ResultAndState<impl HaltReasonTr>,
+        db: &DB,
+        tx_info: TransactionInfo,
+    ) -> Result<GethTrace, DB::Error> {
+        let mut frame = self.try_into_mux_frame(result, db, tx_info)?;
+        let Some(tracer_type) = self.tracer else { return Ok(frame.into()) };
+        Ok(frame.0.remove(&tracer_type).unwrap_or_else(|| NoopFrame::default().into()))
     }
 
     /// Try converting this [MuxInspector] into a [MuxFrame].
@@ -140,6 +211,20 @@ impl MuxInspector {
                         continue;
                     }
                 }
+                TraceConfig::Erc7562(erc7562_config) => {
+                    if let Some(inspector) = &self.tracing {
+                        inspector
+                            .geth_builder()
+                            .geth_erc7562_traces(
+                                erc7562_config.clone(),
+                                result.result.gas_used(),
+                                db,
+                            )
+                            .into()
+                    } else {
+                        continue;
+                    }
+                }
                 TraceConfig::Noop => NoopFrame::default().into(),
             };
 
@@ -174,22 +259,14 @@ where
 
     #[inline]
     fn step(&mut self, interp: &mut Interpreter, context: &mut CTX) {
-        if let Some(ref mut inspector) = self.four_byte {
-            inspector.step(interp, context);
-        }
-        if let Some(ref mut inspector) = self.tracing {
-            inspector.step(interp, context);
-        }
+        forward_step(&mut self.four_byte, interp, context);
+        forward_step(&mut self.tracing, interp, context);
     }
 
     #[inline]
     fn step_end(&mut self, interp: &mut Interpreter, context: &mut CTX) {
-        if let Some(ref mut inspector) = self.four_byte {
-            inspector.step_end(interp, context);
-        }
-        if let Some(ref mut inspector) = self.tracing {
-            inspector.step_end(interp, context);
-        }
+        forward_step_end(&mut self.four_byte, interp, context);
+        forward_step_end(&mut self.tracing, interp, context);
     }
 
     #[inline]
@@ -260,6 +337,51 @@ where
     }
 }
 
+/// Describes which hooks a child inspector of a [MuxInspector] needs.
+trait MuxChild {
+    /// Whether the child inspects the executed opcodes, i.e. needs [Inspector::step] and
+    /// [Inspector::step_end].
+    fn needs_steps(&self) -> bool;
+}
+
+impl MuxChild for FourByteInspector {
+    fn needs_steps(&self) -> bool {
+        false
+    }
+}
+
+impl MuxChild for TracingInspector {
+    fn needs_steps(&self) -> bool {
+        // the call opcodes are inspected for the recorded calls even if no steps are recorded,
+        // the inspector skips the rest of the step itself
+        true
+    }
+}
+
+/// Forwards [Inspector::step] to the child if it needs steps.
+#[inline]
+fn forward_step<CTX, I: Inspector<CTX> + MuxChild>(
+    child: &mut Option<I>,
+    interp: &mut Interpreter,
+    context: &mut CTX,
+) {
+    if let Some(child) = child.as_mut().filter(|child| child.needs_steps()) {
+        child.step(interp, context);
+    }
+}
+
+/// Forwards [Inspector::step_end] to the child if it needs steps.
+#[inline]
+fn forward_step_end<CTX, I: Inspector<CTX> + MuxChild>(
+    child: &mut Option<I>,
+    interp: &mut Interpreter,
+    context: &mut CTX,
+) {
+    if let Some(child) = child.as_mut().filter(|child| child.needs_steps()) {
+        child.step_end(interp, context);
+    }
+}
+
 /// Error type for [MuxInspector]
 #[derive(Debug, Error)]
 pub enum Error {
@@ -269,7 +391,57 @@ pub enum Error {
     /// Expected config is missing
     #[error("expected config is missing for tracer '{0:?}'")]
     MissingConfig(GethDebugBuiltInTracerType),
+    /// The requested tracer is not a known built-in tracer
+    #[error("unknown tracer '{0}'")]
+    UnknownTracer(String),
     /// Error when deserializing the config
     #[error("error deserializing config: {0}")]
     InvalidConfig(#[from] serde_json::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the forwarded steps.
+    #[derive(Default)]
+    struct CountSteps {
+        needs_steps: bool,
+        steps: usize,
+        step_ends: usize,
+    }
+
+    impl MuxChild for CountSteps {
+        fn needs_steps(&self) -> bool {
+            self.needs_steps
+        }
+    }
+
+    impl<CTX> Inspector<CTX> for CountSteps {
+        fn step(&mut self, _interp: &mut Interpreter, _context: &mut CTX) {
+            self.steps += 1;
+        }
+
+        fn step_end(&mut self, _interp: &mut Interpreter, _context: &mut CTX) {
+            self.step_ends += 1;
+        }
+    }
+
+    #[test]
+    fn test_forward_steps() {
+        let mut step_child = Some(CountSteps { needs_steps: true, ..Default::default() });
+        let mut call_child = Some(CountSteps { needs_steps: false, ..Default::default() });
+        let mut interp = Interpreter::default();
+        for _ in 0..3 {
+            for child in [&mut step_child, &mut call_child] {
+                forward_step(child, &mut interp, &mut ());
+                forward_step_end(child, &mut interp, &mut ());
+            }
+        }
+
+        let step_child = step_child.unwrap();
+        assert_eq!((step_child.steps, step_child.step_ends), (3, 3));
+        let call_child = call_child.unwrap();
+        assert_eq!((call_child.steps, call_child.step_ends), (0, 0));
+    }
+}
@@ -17,6 +17,12 @@ impl OpcodeCountInspector {
     pub const fn count(&self) -> usize {
         self.count
     }
+
+    /// Resets the opcode counter to zero.
+    #[inline]
+    pub const fn reset(&mut self) {
+        self.count = 0;
+    }
 }
 
 impl<CTX> Inspector<CTX> for OpcodeCountInspector {
@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+/// The wall-clock time spent inside the callbacks of the
+/// [TracingInspector](super::TracingInspector), see
+/// [TracingInspector::profiling_stats](super::TracingInspector::profiling_stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InspectorProfile {
+    /// The time spent in [Inspector::step](revm::Inspector::step) and
+    /// [Inspector::step_end](revm::Inspector::step_end).
+    pub total_step_time: Duration,
+    /// The time spent in [Inspector::call](revm::Inspector::call),
+    /// [Inspector::create](revm::Inspector::create) and their `_end` counterparts.
+    pub total_call_time: Duration,
+    /// The number of executed opcodes, that is the number of
+    /// [Inspector::step](revm::Inspector::step) callbacks.
+    pub step_count: u64,
+}
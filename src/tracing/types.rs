@@ -9,7 +9,7 @@ use alloc::{
     vec::Vec,
 };
 pub use alloy_primitives::Log;
-use alloy_primitives::{Address, Bytes, FixedBytes, LogData, U256};
+use alloy_primitives::{map::HashMap, Address, Bytes, FixedBytes, LogData, Selector, B256, U256};
 use alloy_rpc_types_trace::{
     geth::{CallFrame, CallLogFrame, GethDefaultTracingOptions, StructLog},
     parity::{
@@ -64,6 +64,28 @@ pub struct CallTrace {
     ///
     /// Note: This is optional because not all tracers make use of this.
     pub maybe_precompile: Option<bool>,
+    /// Whether this is a call to a precompile.
+    ///
+    /// Unlike [`Self::maybe_precompile`], which is only set to exclude the calls to precompiles,
+    /// this is set for every call to a precompile, so the recorded gas and output of precompiles
+    /// can be told apart from regular calls if
+    /// [`TracingInspectorConfig::exclude_precompile_calls`](crate::tracing::TracingInspectorConfig::exclude_precompile_calls)
+    /// is disabled.
+    pub is_precompile: bool,
+    /// Whether this is a call to an account without code, e.g. an externally owned account.
+    ///
+    /// Such a call succeeds without executing anything and returns no data, which is a common
+    /// source of bugs if the caller expects a contract. This is never set for calls to
+    /// precompiles, see
+    /// [`CallTraceArena::calls_to_empty_accounts`](crate::tracing::CallTraceArena::calls_to_empty_accounts).
+    pub is_empty_code: bool,
+    /// The decoded input of a call to a well-known precompile, which determines the gas the
+    /// precompile charges.
+    ///
+    /// This is only recorded if
+    /// [`TracingInspectorConfig::record_precompile_inputs`](crate::tracing::TracingInspectorConfig::record_precompile_inputs)
+    /// is enabled.
+    pub precompile_input: Option<PrecompileInput>,
     /// The address of the selfdestructed contract.
     pub selfdestruct_address: Option<Address>,
     /// Holds the target for the selfdestruct refund target.
@@ -80,20 +102,122 @@ pub struct CallTrace {
     ///
     /// See [`is_selfdestruct`](Self::is_selfdestruct) for more information.
     pub selfdestruct_transferred_value: Option<U256>,
+    /// Whether the selfdestruct deleted the contract.
+    ///
+    /// Since the Cancun hardfork a selfdestruct only deletes a contract that was created in the
+    /// same transaction, see [EIP-6780](https://eips.ethereum.org/EIPS/eip-6780). Otherwise it
+    /// only transfers the balance to the refund target.
+    ///
+    /// This is only `Some` if a selfdestruct was executed, see also [`Self::selfdestruct`].
+    pub selfdestruct_destroyed: Option<bool>,
     /// The kind of call.
     pub kind: CallKind,
     /// The value transferred in the call.
     pub value: U256,
+    /// The value minted by an OP-stack deposit transaction.
+    ///
+    /// This is only set for the root call, see
+    /// [`TracingInspector::set_deposit_transaction`](crate::tracing::TracingInspector::set_deposit_transaction).
+    #[cfg(feature = "optimism")]
+    pub mint: Option<U256>,
+    /// The source hash of an OP-stack deposit transaction.
+    ///
+    /// This is only set for the root call, see
+    /// [`TracingInspector::set_deposit_transaction`](crate::tracing::TracingInspector::set_deposit_transaction).
+    #[cfg(feature = "optimism")]
+    pub source_hash: Option<B256>,
     /// The calldata/input, or the init code for contract creations.
     pub data: Bytes,
+    /// The size of the calldata/input.
+    ///
+    /// This is recorded even if the [`Self::data`] itself is not, see
+    /// [`DataCaptureMode`](crate::tracing::DataCaptureMode).
+    pub input_size: u64,
+    /// The memory offset of the calldata in the caller's memory, if the call was made by a call
+    /// opcode.
+    pub args_offset: Option<usize>,
+    /// The size of the calldata in the caller's memory, if the call was made by a call opcode.
+    ///
+    /// A call that forwards its own calldata, like a proxy, usually copies it to memory at
+    /// offset `0` and passes the full size.
+    pub args_size: Option<usize>,
+    /// The keccak256 hash of the calldata/input.
+    ///
+    /// This is only recorded if
+    /// [`TracingInspectorConfig::record_data_hashes`](crate::tracing::TracingInspectorConfig::record_data_hashes)
+    /// is enabled.
+    pub input_hash: Option<B256>,
+    /// The salt of a [`CallKind::Create2`].
+    pub create2_salt: Option<U256>,
+    /// The hash of the init code of a [`CallKind::Create2`].
+    ///
+    /// Together with the [`Self::create2_salt`] and the [`Self::caller`] this derives the
+    /// [`Self::address`] of the created contract.
+    pub init_code_hash: Option<B256>,
+    /// The init code of a contract creation, including any appended constructor arguments.
+    ///
+    /// This is the same as [`Self::data`] but only set for [`CallKind::Create`] and alike, so
+    /// consumers don't have to check the kind of the call. Like the data it's not recorded if
+    /// only the sizes are captured, see [`DataCaptureMode`](crate::tracing::DataCaptureMode).
+    pub init_code: Option<Bytes>,
     /// The return data, or the runtime bytecode of the created contract.
     pub output: Bytes,
+    /// The size of the return data.
+    ///
+    /// This is recorded even if the [`Self::output`] itself is not, see
+    /// [`DataCaptureMode`](crate::tracing::DataCaptureMode).
+    pub output_size: u64,
+    /// The keccak256 hash of the return data.
+    ///
+    /// This is only recorded if
+    /// [`TracingInspectorConfig::record_data_hashes`](crate::tracing::TracingInspectorConfig::record_data_hashes)
+    /// is enabled.
+    pub output_hash: Option<B256>,
+    /// The decoded revert reason of the subcall this call forwarded its revert from, if it
+    /// reverted without return data of its own.
+    ///
+    /// This is set for a call that reverts with empty return data after a subcall reverted with a
+    /// reason, e.g. because the return value of the subcall was checked. See also
+    /// [`CallTraceArena::revert_origin`](crate::tracing::CallTraceArena::revert_origin).
+    ///
+    /// The reason is decoded from the recorded output of the subcall, so this is only set if the
+    /// [`DataCaptureMode`](crate::tracing::DataCaptureMode) is `Full`.
+    pub forwarded_revert_reason: Option<String>,
+    /// The selector and the ABI-encoded arguments of the custom error the call reverted with.
+    ///
+    /// This is only set if the return data of a reverted call is not an `Error(string)` or
+    /// `Panic(uint256)`, so the error can be looked up by its selector.
+    pub custom_error: Option<(Selector, Bytes)>,
     /// The total gas cost of the call.
     pub gas_used: u64,
-    /// The gas limit of the call.
+    /// The gas limit of the call, i.e. the gas available when the call started executing.
+    ///
+    /// For a subcall this is the gas forwarded by the caller, which can be less than the gas
+    /// requested by the call opcode because at most 63/64 of the remaining gas of the caller are
+    /// forwarded, see [EIP-150](https://eips.ethereum.org/EIPS/eip-150).
     pub gas_limit: u64,
+    /// The gas available to the caller when it made the call, after the costs of the call opcode
+    /// itself, such as the account access, were deducted.
+    ///
+    /// Only set for calls made by a call opcode, see [`GasForward`].
+    pub caller_gas: Option<u64>,
+    /// The gas requested by the call opcode, which caps the gas forwarded to the call.
+    ///
+    /// Only set for calls made by a call opcode, see [`GasForward`].
+    pub requested_gas: Option<u64>,
+    /// The gas refund accumulated by the call, including the refunds of successful subcalls.
+    ///
+    /// This is the raw refund counter of the call and not capped by the refund quotient of the
+    /// transaction yet, see also [EIP-3529](https://eips.ethereum.org/EIPS/eip-3529).
+    pub refund: u64,
     /// The final status of the call.
     pub status: Option<InstructionResult>,
+    /// The state modifying opcode, e.g. `SSTORE`, that was executed inside a static call.
+    ///
+    /// This is only set if the call failed because of it, see
+    /// [`TraceError::StaticCallViolation`].
+    #[cfg_attr(feature = "serde", serde(with = "option_opcode_serde"))]
+    pub static_call_violation: Option<OpCode>,
     /// Opcode-level execution steps.
     pub steps: Vec<CallTraceStep>,
     /// Optional complementary decoded call data.
@@ -132,6 +256,26 @@ impl CallTrace {
             || self.selfdestruct_refund_target.is_some()
     }
 
+    /// Returns the selfdestruct executed in this call, if any.
+    pub fn selfdestruct(&self) -> Option<Selfdestruct> {
+        Some(Selfdestruct {
+            contract: self.selfdestruct_address?,
+            beneficiary: self.selfdestruct_refund_target?,
+            balance: self.selfdestruct_transferred_value.unwrap_or_default(),
+            actually_destroyed: self.selfdestruct_destroyed.unwrap_or_default(),
+        })
+    }
+
+    /// Returns the typed error if it is an erroneous result.
+    pub fn error(&self) -> Option<TraceError> {
+        self.status.and_then(TraceError::from_instruction_result).map(|err| match err {
+            TraceError::StaticCallViolation(None) => {
+                TraceError::StaticCallViolation(self.static_call_violation)
+            }
+            err => err,
+        })
+    }
+
     /// Returns the error message if it is an erroneous result.
     pub(crate) fn as_error_msg(&self, kind: TraceStyle) -> Option<String> {
         self.status.and_then(|status| utils::fmt_error_msg(status, kind))
@@ -160,6 +304,124 @@ impl CallTrace {
     }
 }
 
+/// A selfdestruct executed in a call, see [`CallTrace::selfdestruct`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Selfdestruct {
+    /// The address of the selfdestructed contract.
+    pub contract: Address,
+    /// The account that received the balance of the contract.
+    pub beneficiary: Address,
+    /// The balance transferred to the beneficiary.
+    pub balance: U256,
+    /// Whether the contract was deleted, see [`CallTrace::selfdestruct_destroyed`].
+    pub actually_destroyed: bool,
+}
+
+/// The decoded input of a call to a well-known precompile, see
+/// [`CallTrace::precompile_input`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PrecompileInput {
+    /// The operand lengths of the `modexp` precompile at `0x05`, see
+    /// [EIP-198](https://eips.ethereum.org/EIPS/eip-198).
+    ///
+    /// Missing bytes of the input are zero and lengths that don't fit into a `u64` are saturated.
+    ModExp {
+        /// The length of the base in bytes.
+        base_len: u64,
+        /// The length of the exponent in bytes.
+        exp_len: u64,
+        /// The length of the modulus in bytes.
+        mod_len: u64,
+    },
+    /// The number of pairs checked by the `ecPairing` precompile at `0x08`, see
+    /// [EIP-197](https://eips.ethereum.org/EIPS/eip-197).
+    EcPairing {
+        /// The number of complete 192 byte pairs in the input.
+        pairs: usize,
+    },
+}
+
+impl PrecompileInput {
+    /// The address of the `modexp` precompile.
+    pub const MODEXP: Address = Address::with_last_byte(0x05);
+    /// The address of the `ecPairing` precompile.
+    pub const EC_PAIRING: Address = Address::with_last_byte(0x08);
+
+    /// Decodes the input of a call to the precompile at `address`.
+    ///
+    /// Returns `None` if the precompile is not supported.
+    pub fn decode(address: Address, input: &[u8]) -> Option<Self> {
+        match address {
+            Self::MODEXP => {
+                let len = |word: usize| {
+                    let mut bytes = [0u8; 32];
+                    let start = (word * 32).min(input.len());
+                    let end = (start + 32).min(input.len());
+                    bytes[..end - start].copy_from_slice(&input[start..end]);
+                    U256::from_be_bytes(bytes).saturating_to()
+                };
+                Some(Self::ModExp { base_len: len(0), exp_len: len(1), mod_len: len(2) })
+            }
+            Self::EC_PAIRING => Some(Self::EcPairing { pairs: input.len() / 192 }),
+            _ => None,
+        }
+    }
+}
+
+/// A snapshot of the execution at a breakpoint, see
+/// [`TracingInspector::with_breakpoints`](crate::tracing::TracingInspector::with_breakpoints).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreakpointSnapshot {
+    /// The address of the executed code.
+    ///
+    /// This differs from the [`Self::address`] for a `DELEGATECALL`.
+    pub code_address: Address,
+    /// The program counter of the breakpoint.
+    pub pc: u64,
+    /// The address of the contract whose context the code executes in.
+    pub address: Address,
+    /// The index of the call in the arena.
+    pub frame: usize,
+    /// The stack before the opcode at the breakpoint is executed, with the top of the stack
+    /// last.
+    pub stack: Vec<U256>,
+    /// The memory before the opcode at the breakpoint is executed.
+    pub memory: RecordedMemory,
+    /// The remaining gas before the opcode at the breakpoint is executed.
+    pub gas_remaining: u64,
+}
+
+/// The gas a call opcode forwarded to a call, see
+/// [`CallTraceArena::gas_forwarding`](crate::tracing::CallTraceArena::gas_forwarding).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GasForward {
+    /// The index of the call in the arena.
+    pub idx: usize,
+    /// The gas available to the caller, see [`CallTrace::caller_gas`].
+    pub caller_gas: u64,
+    /// The gas requested by the call opcode, see [`CallTrace::requested_gas`].
+    pub requested_gas: u64,
+    /// The stipend given to the call on top of the forwarded gas if it transfers value.
+    pub stipend: u64,
+    /// The gas the call started with, including the stipend.
+    pub gas_given: u64,
+}
+
+impl GasForward {
+    /// Returns the gas the call should have been given according to
+    /// [EIP-150](https://eips.ethereum.org/EIPS/eip-150): the requested gas, but at most all but
+    /// one 64th of the gas available to the caller, plus the stipend.
+    pub const fn expected_gas(&self) -> u64 {
+        let max = self.caller_gas - self.caller_gas / 64;
+        let forwarded = if self.requested_gas < max { self.requested_gas } else { max };
+        forwarded + self.stipend
+    }
+}
+
 /// Additional decoded data enhancing the [CallLog].
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -241,6 +503,9 @@ pub struct CallTraceNode {
     pub logs: Vec<CallLog>,
     /// Ordering of child calls and logs
     pub ordering: Vec<TraceMemberOrder>,
+    /// Custom metadata attached to the call, e.g. by the callback registered with
+    /// [TracingInspector::on_frame](crate::tracing::TracingInspector::on_frame).
+    pub metadata: Option<serde_json::Value>,
 }
 
 impl CallTraceNode {
@@ -255,6 +520,14 @@ impl CallTraceNode {
         }
     }
 
+    /// Returns the address of the contract whose code is executed.
+    ///
+    /// This only differs from the [execution address](Self::execution_address), whose storage is
+    /// used, for delegate calls.
+    pub const fn code_address(&self) -> Address {
+        self.trace.address
+    }
+
     /// Pushes all steps onto the stack in reverse order
     /// so that the first step is on top of the stack
     pub(crate) fn push_steps_on_stack<'a>(
@@ -313,6 +586,48 @@ impl CallTraceNode {
         self.trace.status
     }
 
+    /// Returns the address of the deployed contract if this is a successful
+    /// [`CallKind::Create`] or [`CallKind::Create2`].
+    #[inline]
+    pub const fn created_address(&self) -> Option<Address> {
+        if self.kind().is_any_create() && self.trace.success {
+            Some(self.trace.address)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the size of the deployed runtime code if this is a successful
+    /// [`CallKind::Create`] or [`CallKind::Create2`].
+    ///
+    /// For creates, the output of the trace is the runtime code returned by the init code. The
+    /// size is known even if the output itself is not captured.
+    #[inline]
+    pub fn deployed_code_size(&self) -> Option<usize> {
+        self.created_address().map(|_| self.trace.output_size as usize)
+    }
+
+    /// Returns all `JUMP`/`JUMPI` steps of this call that attempted to jump to a destination that
+    /// is not a `JUMPDEST`, as `(step_index, attempted_destination)`.
+    ///
+    /// Note: this requires stack snapshots to be recorded, see
+    /// [TracingInspectorConfig::stack_snapshots](crate::tracing::TracingInspectorConfig::stack_snapshots).
+    pub fn invalid_jumps(&self) -> Vec<(usize, u64)> {
+        self.trace
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| {
+                matches!(step.op.get(), opcode::JUMP | opcode::JUMPI)
+                    && step.status == Some(InstructionResult::InvalidJump)
+            })
+            .filter_map(|(idx, step)| {
+                let dest = step.stack.as_ref()?.last()?;
+                Some((idx, dest.saturating_to()))
+            })
+            .collect()
+    }
+
     /// Returns the call context's 4 byte selector
     pub fn selector(&self) -> Option<FixedBytes<4>> {
         (self.trace.data.len() >= 4).then(|| FixedBytes::from_slice(&self.trace.data[..4]))
@@ -622,6 +937,45 @@ pub enum TraceMemberOrder {
     Step(usize),
 }
 
+/// An event of a recorded call, see
+/// [`CallTraceArena::into_events`](crate::tracing::CallTraceArena::into_events).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraceEvent {
+    /// A call started.
+    Enter {
+        /// The index of the call in the arena.
+        idx: usize,
+        /// The call, without its steps, which are emitted as [`TraceEvent::Step`].
+        trace: Box<CallTrace>,
+    },
+    /// A call ended.
+    Exit {
+        /// The index of the call in the arena.
+        idx: usize,
+        /// The final status of the call, see [`CallTrace::status`].
+        status: Option<InstructionResult>,
+        /// The return data, or the runtime bytecode of the created contract.
+        output: Bytes,
+        /// The total gas cost of the call.
+        gas_used: u64,
+    },
+    /// A call emitted a log.
+    Log {
+        /// The index of the call in the arena.
+        idx: usize,
+        /// The emitted log.
+        log: CallLog,
+    },
+    /// A call executed an opcode.
+    Step {
+        /// The index of the call in the arena.
+        idx: usize,
+        /// The executed step.
+        step: Box<CallTraceStep>,
+    },
+}
+
 /// Represents a decoded internal function call.
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -647,6 +1001,21 @@ pub enum DecodedTraceStep {
     Line(String),
 }
 
+/// A location in a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceLocation {
+    /// The path of the source file.
+    pub file: String,
+    /// The line in the source file, starting at 1.
+    pub line: u32,
+    /// The column in the line, starting at 1.
+    pub column: u32,
+}
+
+/// Maps the program counters of a contract's code to their [SourceLocation]s.
+pub type SourceMap = HashMap<usize, SourceLocation>;
+
 /// Represents a tracked call step during execution
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -684,6 +1053,11 @@ pub struct CallTraceStep {
     pub status: Option<InstructionResult>,
     /// Immediate bytes of the step
     pub immediate_bytes: Option<Bytes>,
+    /// Source location of the step.
+    ///
+    /// This is only set if a [SourceMap] of the executed code is provided, see
+    /// [TracingInspector::with_source_map](crate::tracing::TracingInspector::with_source_map).
+    pub source_location: Option<Box<SourceLocation>>,
     /// Optional complementary decoded step data.
     pub decoded: Option<Box<DecodedTraceStep>>,
 }
@@ -756,6 +1130,27 @@ impl CallTraceStep {
         status.is_error()
     }
 
+    /// Returns the typed error if it is an erroneous result.
+    #[inline]
+    pub fn error(&self) -> Option<TraceError> {
+        self.status.filter(|status| status.is_error()).and_then(TraceError::from_instruction_result)
+    }
+
+    /// Returns the value pushed onto the stack if this is a `PUSHn` step.
+    ///
+    /// The value of `PUSH1` to `PUSH32` is read from the [immediate bytes](Self::immediate_bytes),
+    /// so it's only available if they are recorded, see
+    /// [TracingInspectorConfig::record_immediate_bytes](crate::tracing::TracingInspectorConfig::record_immediate_bytes).
+    pub fn push_value(&self) -> Option<U256> {
+        match self.op.get() {
+            opcode::PUSH0 => Some(U256::ZERO),
+            opcode::PUSH1..=opcode::PUSH32 => {
+                self.immediate_bytes.as_ref().map(|bytes| U256::from_be_slice(bytes))
+            }
+            _ => None,
+        }
+    }
+
     /// Returns the error message if it is an erroneous result.
     #[inline]
     pub(crate) fn as_error(&self) -> Option<String> {
@@ -768,6 +1163,129 @@ impl CallTraceStep {
     }
 }
 
+/// A typed error of a failed call frame or step.
+///
+/// The [`Display`](core::fmt::Display) impl matches the error strings used by geth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceError {
+    /// Execution reverted.
+    Revert,
+    /// Ran out of gas.
+    OutOfGas,
+    /// Ran out of gas while expanding memory.
+    MemoryOutOfGas,
+    /// Reached the memory limit.
+    MemoryLimitOutOfGas,
+    /// Ran out of gas because of an invalid operand.
+    InvalidOperandOutOfGas,
+    /// Not enough gas left for the reentrancy sentry.
+    ReentrancySentryOutOfGas,
+    /// Not enough balance for the value transfer.
+    OutOfFunds,
+    /// Undefined opcode.
+    InvalidOpcode,
+    /// The designated `INVALID` (`0xfe`) opcode.
+    InvalidFEOpcode,
+    /// Popped from an empty stack.
+    StackUnderflow,
+    /// Pushed onto a full stack.
+    StackOverflow,
+    /// Jump to an invalid destination.
+    InvalidJump,
+    /// A precompile failed.
+    PrecompileError,
+    /// The deployed code exceeds the code size limit of
+    /// [EIP-170](https://eips.ethereum.org/EIPS/eip-170).
+    CreateContractSizeLimit,
+    /// The init code exceeds the init code size limit of
+    /// [EIP-3860](https://eips.ethereum.org/EIPS/eip-3860).
+    CreateInitCodeSizeLimit,
+    /// The deployed code starts with the `0xef` byte reserved by
+    /// [EIP-3541](https://eips.ethereum.org/EIPS/eip-3541).
+    CreateContractStartingWithEF,
+    /// A state modifying opcode was executed inside a static call, with the opcode if it is
+    /// known.
+    ///
+    /// The opcode is only known for errors of a [`CallTrace`], see
+    /// [`CallTrace::static_call_violation`].
+    StaticCallViolation(Option<OpCode>),
+    /// Any other failure, displayed as the raw [`InstructionResult`].
+    Other(InstructionResult),
+}
+
+impl TraceError {
+    /// Converts a non successful [`InstructionResult`] to a [`TraceError`].
+    ///
+    /// Returns `None` if [`InstructionResult::is_ok`].
+    pub const fn from_instruction_result(res: InstructionResult) -> Option<Self> {
+        if res.is_ok() {
+            return None;
+        }
+        let err = match res {
+            InstructionResult::Revert => Self::Revert,
+            InstructionResult::OutOfGas | InstructionResult::PrecompileOOG => Self::OutOfGas,
+            InstructionResult::MemoryOOG => Self::MemoryOutOfGas,
+            InstructionResult::MemoryLimitOOG => Self::MemoryLimitOutOfGas,
+            InstructionResult::InvalidOperandOOG => Self::InvalidOperandOutOfGas,
+            InstructionResult::ReentrancySentryOOG => Self::ReentrancySentryOutOfGas,
+            InstructionResult::OutOfFunds => Self::OutOfFunds,
+            InstructionResult::OpcodeNotFound => Self::InvalidOpcode,
+            InstructionResult::InvalidFEOpcode => Self::InvalidFEOpcode,
+            InstructionResult::StackUnderflow => Self::StackUnderflow,
+            InstructionResult::StackOverflow => Self::StackOverflow,
+            InstructionResult::InvalidJump => Self::InvalidJump,
+            InstructionResult::PrecompileError => Self::PrecompileError,
+            InstructionResult::CreateContractSizeLimit => Self::CreateContractSizeLimit,
+            InstructionResult::CreateInitCodeSizeLimit => Self::CreateInitCodeSizeLimit,
+            InstructionResult::CreateContractStartingWithEF => Self::CreateContractStartingWithEF,
+            InstructionResult::StateChangeDuringStaticCall
+            | InstructionResult::CallNotAllowedInsideStatic => Self::StaticCallViolation(None),
+            status => Self::Other(status),
+        };
+        Some(err)
+    }
+}
+
+impl TraceError {
+    /// Returns true if this is any of the out of gas errors.
+    pub const fn is_out_of_gas(&self) -> bool {
+        matches!(
+            self,
+            Self::OutOfGas
+                | Self::MemoryOutOfGas
+                | Self::MemoryLimitOutOfGas
+                | Self::InvalidOperandOutOfGas
+                | Self::ReentrancySentryOutOfGas
+        )
+    }
+}
+
+impl core::fmt::Display for TraceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let msg = match self {
+            Self::Revert => "execution reverted",
+            Self::OutOfGas => "out of gas",
+            Self::MemoryOutOfGas => "out of gas: out of memory",
+            Self::MemoryLimitOutOfGas => "out of gas: reach memory limit",
+            Self::InvalidOperandOutOfGas => "out of gas: invalid operand",
+            Self::ReentrancySentryOutOfGas => "out of gas: not enough gas for reentrancy sentry",
+            Self::OutOfFunds => "insufficient balance for transfer",
+            Self::InvalidOpcode => "invalid opcode",
+            Self::InvalidFEOpcode => "invalid opcode: INVALID",
+            Self::StackUnderflow => "stack underflow",
+            Self::StackOverflow => "Out of stack",
+            Self::InvalidJump => "invalid jump destination",
+            Self::PrecompileError => "precompiled failed",
+            Self::CreateContractSizeLimit => "max code size exceeded",
+            Self::CreateInitCodeSizeLimit => "max initcode size exceeded",
+            Self::CreateContractStartingWithEF => "invalid code: must not begin with 0xef",
+            Self::StaticCallViolation(_) => "write protection",
+            Self::Other(status) => return write!(f, "{status:?}"),
+        };
+        f.write_str(msg)
+    }
+}
+
 /// Represents the source of a storage change - e.g., whether it came
 /// from an SSTORE or SLOAD instruction.
 #[allow(clippy::upper_case_acronyms)]
@@ -856,6 +1374,26 @@ impl AsRef<[u8]> for RecordedMemory {
     }
 }
 
+#[cfg(feature = "serde")]
+mod option_opcode_serde {
+    use super::OpCode;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S>(op: &Option<OpCode>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        op.map(|op| op.get()).serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Option<OpCode>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<u8>::deserialize(deserializer)?.and_then(OpCode::new))
+    }
+}
+
 #[cfg(feature = "serde")]
 mod opcode_serde {
     use super::OpCode;
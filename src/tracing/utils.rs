@@ -1,12 +1,14 @@
 //! Utility functions for revm related ops
-use crate::tracing::config::TraceStyle;
+use crate::tracing::{config::TraceStyle, types::TraceError};
 use alloc::{
     format,
     string::{String, ToString},
     vec::Vec,
 };
-use alloy_primitives::{hex, Bytes};
-use alloy_sol_types::{ContractError, GenericRevertReason};
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::Function;
+use alloy_primitives::{hex, Address, Bytes, Selector, B256, U256};
+use alloy_sol_types::{ContractError, GenericRevertReason, Panic, Revert, SolError};
 use revm::{
     interpreter::InstructionResult,
     primitives::{hardfork::SpecId, KECCAK_EMPTY},
@@ -19,56 +21,26 @@ use revm::{
 ///
 /// See also <https://github.com/ethereum/go-ethereum/blob/34d507215951fb3f4a5983b65e127577989a6db8/eth/tracers/native/call_flat.go#L39-L55>
 pub(crate) fn fmt_error_msg(res: InstructionResult, kind: TraceStyle) -> Option<String> {
-    if res.is_ok() {
-        return None;
+    let err = TraceError::from_instruction_result(res)?;
+    if !kind.is_parity() {
+        return Some(err.to_string());
     }
-    let msg = match res {
-        InstructionResult::Revert => {
-            if kind.is_parity() { "Reverted" } else { "execution reverted" }.to_string()
-        }
-        InstructionResult::OutOfGas | InstructionResult::PrecompileOOG => {
-            if kind.is_parity() { "Out of gas" } else { "out of gas" }.to_string()
-        }
-        InstructionResult::OutOfFunds => if kind.is_parity() {
-            "Insufficient balance for transfer"
-        } else {
-            "insufficient balance for transfer"
-        }
-        .to_string(),
-        InstructionResult::MemoryOOG => {
-            if kind.is_parity() { "Out of gas" } else { "out of gas: out of memory" }.to_string()
-        }
-        InstructionResult::MemoryLimitOOG => {
-            if kind.is_parity() { "Out of gas" } else { "out of gas: reach memory limit" }
-                .to_string()
-        }
-        InstructionResult::InvalidOperandOOG => {
-            if kind.is_parity() { "Out of gas" } else { "out of gas: invalid operand" }.to_string()
-        }
-        InstructionResult::OpcodeNotFound => {
-            if kind.is_parity() { "Bad instruction" } else { "invalid opcode" }.to_string()
-        }
-        InstructionResult::StackOverflow => "Out of stack".to_string(),
-        InstructionResult::InvalidJump => {
-            if kind.is_parity() { "Bad jump destination" } else { "invalid jump destination" }
-                .to_string()
-        }
-        InstructionResult::PrecompileError => {
-            if kind.is_parity() { "Built-in failed" } else { "precompiled failed" }.to_string()
-        }
-        InstructionResult::InvalidFEOpcode => {
-            if kind.is_parity() { "Bad instruction" } else { "invalid opcode: INVALID" }.to_string()
-        }
-        InstructionResult::ReentrancySentryOOG => if kind.is_parity() {
-            "Out of gas"
-        } else {
-            "out of gas: not enough gas for reentrancy sentry"
-        }
-        .to_string(),
-        status => format!("{status:?}"),
+    let msg = match err {
+        TraceError::Revert => "Reverted",
+        TraceError::OutOfGas
+        | TraceError::MemoryOutOfGas
+        | TraceError::MemoryLimitOutOfGas
+        | TraceError::InvalidOperandOutOfGas
+        | TraceError::ReentrancySentryOutOfGas => "Out of gas",
+        TraceError::OutOfFunds => "Insufficient balance for transfer",
+        TraceError::InvalidOpcode | TraceError::InvalidFEOpcode => "Bad instruction",
+        TraceError::InvalidJump => "Bad jump destination",
+        TraceError::PrecompileError => "Built-in failed",
+        TraceError::StaticCallViolation(_) => "Mutable Call In Static Context",
+        err => return Some(err.to_string()),
     };
 
-    Some(msg)
+    Some(msg.to_string())
 }
 
 /// Formats memory data into a list of 32-byte hex-encoded chunks.
@@ -133,6 +105,62 @@ pub(crate) fn maybe_revert_reason(output: &[u8]) -> Option<String> {
     }
 }
 
+/// Returns the selector and the ABI-encoded arguments if the output is a custom error, e.g.
+/// `error Unauthorized(address caller)`.
+///
+/// Returns `None` for the standard `Error(string)` and `Panic(uint256)` errors.
+#[inline]
+pub(crate) fn maybe_custom_error(output: &[u8]) -> Option<(Selector, Bytes)> {
+    if output.len() < 4 || (output.len() - 4) % 32 != 0 {
+        return None;
+    }
+    let (selector, data) = output.split_at(4);
+    let selector = Selector::from_slice(selector);
+    if selector == Revert::SELECTOR || selector == Panic::SELECTOR {
+        return None;
+    }
+    Some((selector, Bytes::copy_from_slice(data)))
+}
+
+/// Returns the address of the contract that a `CREATE2` of the `deployer` creates, before the
+/// creation is executed.
+///
+/// The address only depends on the deployer, the salt and the keccak256 hash of the init code,
+/// see [EIP-1014](https://eips.ethereum.org/EIPS/eip-1014).
+#[inline]
+pub fn predict_create2_address(deployer: Address, salt: U256, init_code_hash: B256) -> Address {
+    deployer.create2(B256::from(salt), init_code_hash)
+}
+
+/// Decodes the ABI-encoded arguments of a call to the given function and formats each of them,
+/// see [format_abi_value].
+pub(crate) fn decode_call_args(
+    function: &Function,
+    data: &[u8],
+) -> Result<Vec<String>, alloy_dyn_abi::Error> {
+    Ok(function.abi_decode_input(data)?.iter().map(format_abi_value).collect())
+}
+
+/// Formats a decoded ABI value, integers as decimals and bytes as hex.
+fn format_abi_value(value: &DynSolValue) -> String {
+    let join =
+        |values: &[DynSolValue]| values.iter().map(format_abi_value).collect::<Vec<_>>().join(", ");
+    match value {
+        DynSolValue::Bool(value) => value.to_string(),
+        DynSolValue::Int(value, _) => value.to_string(),
+        DynSolValue::Uint(value, _) => value.to_string(),
+        DynSolValue::FixedBytes(word, size) => hex::encode_prefixed(&word[..*size]),
+        DynSolValue::Address(address) => address.to_string(),
+        DynSolValue::Function(function) => function.to_string(),
+        DynSolValue::Bytes(bytes) => hex::encode_prefixed(bytes),
+        DynSolValue::String(value) => format!("{value:?}"),
+        DynSolValue::Array(values) => format!("[{}]", join(values)),
+        DynSolValue::FixedArray(values) => format!("[{}]", join(values)),
+        // tuples and structs
+        _ => format!("({})", join(value.as_fixed_seq().unwrap_or_default())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,14 +1,17 @@
 use super::{
+    arena::revert_origin,
     types::{
         CallKind, CallLog, CallTrace, CallTraceNode, DecodedCallData, DecodedTraceStep,
         TraceMemberOrder,
     },
+    utils::maybe_revert_reason,
     CallTraceArena,
 };
-use alloc::{format, string::String, vec::Vec};
+use alloc::{format, string::String, sync::Arc, vec, vec::Vec};
 use alloy_primitives::{address, hex, map::HashMap, Address, B256, U256};
 use anstyle::{AnsiColor, Color, Style};
 use colorchoice::ColorChoice;
+use core::fmt;
 use revm::interpreter::InstructionResult;
 use std::io::{self, Write};
 
@@ -17,6 +20,7 @@ const CHEATCODE_ADDRESS: Address = address!("7109709ECfa91a80626fF3989D68f67F5b1
 const PIPE: &str = "  │ ";
 const EDGE: &str = "  └─ ";
 const BRANCH: &str = "  ├─ ";
+const ELLIPSIS: &str = "…";
 const CALL: &str = "→ ";
 const RETURN: &str = "← ";
 
@@ -31,6 +35,8 @@ pub struct TraceWriterConfig {
     color_cheatcodes: bool,
     write_bytecodes: bool,
     write_storage_changes: bool,
+    bubble_revert_reasons: bool,
+    max_bytes: Option<usize>,
 }
 
 impl Default for TraceWriterConfig {
@@ -47,6 +53,8 @@ impl TraceWriterConfig {
             color_cheatcodes: false,
             write_bytecodes: false,
             write_storage_changes: false,
+            bubble_revert_reasons: false,
+            max_bytes: None,
         }
     }
 
@@ -94,6 +102,40 @@ impl TraceWriterConfig {
     pub fn get_write_storage_changes(&self) -> bool {
         self.write_storage_changes
     }
+
+    /// Sets whether reverted calls that forward the revert of a subcall reference the revert
+    /// reason and address of the call it originated from. Default: false.
+    pub fn bubble_revert_reasons(mut self, yes: bool) -> Self {
+        self.bubble_revert_reasons = yes;
+        self
+    }
+
+    /// Returns `true` if the origin of forwarded revert reasons is written.
+    pub fn get_bubble_revert_reasons(&self) -> bool {
+        self.bubble_revert_reasons
+    }
+
+    /// Sets the maximum number of bytes of call data, return data and log data to write, the
+    /// rest is replaced with the number of omitted bytes. Default: None.
+    pub fn max_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Returns the maximum number of bytes of call data, return data and log data to write.
+    pub fn get_max_bytes(&self) -> Option<usize> {
+        self.max_bytes
+    }
+}
+
+/// A predicate selecting the frames written by a [`TraceWriter`].
+#[derive(Clone)]
+struct FrameFilter(Arc<dyn Fn(&CallTraceNode) -> bool + Send + Sync>);
+
+impl fmt::Debug for FrameFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FrameFilter").finish_non_exhaustive()
+    }
 }
 
 /// Formats [call traces](CallTraceArena) to an [`Write`] writer.
@@ -104,6 +146,9 @@ pub struct TraceWriter<W> {
     writer: W,
     indentation_level: u16,
     config: TraceWriterConfig,
+    frame_filter: Option<FrameFilter>,
+    /// Whether each node of the arena that is being written is visible with the frame filter.
+    visible: Vec<bool>,
 }
 
 impl<W: Write> TraceWriter<W> {
@@ -115,7 +160,7 @@ impl<W: Write> TraceWriter<W> {
 
     /// Create a new `TraceWriter` with the given writer and configuration.
     pub fn with_config(writer: W, config: TraceWriterConfig) -> Self {
-        Self { writer, indentation_level: 0, config }
+        Self { writer, indentation_level: 0, config, frame_filter: None, visible: Vec::new() }
     }
 
     /// Sets the color choice.
@@ -153,6 +198,33 @@ impl<W: Write> TraceWriter<W> {
         self
     }
 
+    /// Sets whether to write the origin of forwarded revert reasons.
+    #[inline]
+    pub fn bubble_revert_reasons(mut self, yes: bool) -> Self {
+        self.config.bubble_revert_reasons = yes;
+        self
+    }
+
+    /// Sets the maximum number of bytes of call data, return data and log data to write.
+    #[inline]
+    pub fn with_max_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.config.max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets a predicate to only write the frames it matches.
+    ///
+    /// The ancestors of matched frames are still written to preserve the call tree, subcalls
+    /// without any matched frame are replaced with a `…` marker.
+    #[inline]
+    pub fn with_frame_filter(
+        mut self,
+        filter: impl Fn(&CallTraceNode) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.frame_filter = Some(FrameFilter(Arc::new(filter)));
+        self
+    }
+
     /// Returns a reference to the inner writer.
     #[inline]
     pub const fn writer(&self) -> &W {
@@ -173,10 +245,56 @@ impl<W: Write> TraceWriter<W> {
 
     /// Writes a call trace arena to the writer.
     pub fn write_arena(&mut self, arena: &CallTraceArena) -> io::Result<()> {
+        self.visible = self.visible_nodes(arena.nodes());
         self.write_node(arena.nodes(), 0)?;
         self.writer.flush()
     }
 
+    /// Writes a call trace arena to the writer as [JSON lines](https://jsonlines.org).
+    ///
+    /// Each call is written as a single JSON object on its own line, in the order the calls
+    /// were made, with the fields `depth`, `from`, `to`, `gasUsed`, `input` (the function
+    /// selector, if any) and `error`. Unlike [`Self::write_arena`], this ignores the colors and
+    /// the indentation level, the frame filter is applied to each call individually.
+    pub fn write_arena_jsonl(&mut self, arena: &CallTraceArena) -> io::Result<()> {
+        for node in arena.nodes() {
+            if self.frame_filter.as_ref().is_some_and(|FrameFilter(filter)| !filter(node)) {
+                continue;
+            }
+            let trace = &node.trace;
+            let selector = (!trace.kind.is_any_create() && trace.data.len() >= 4)
+                .then(|| hex::encode_prefixed(&trace.data[..4]));
+            let line = serde_json::json!({
+                "depth": trace.depth,
+                "from": trace.caller,
+                "to": trace.address,
+                "gasUsed": trace.gas_used,
+                "input": selector,
+                "error": trace.error().map(|err| err.to_string()),
+            });
+            serde_json::to_writer(&mut self.writer, &line)?;
+            writeln!(self.writer)?;
+        }
+        self.writer.flush()
+    }
+
+    /// Returns whether each node matches the frame filter or has a matching descendant.
+    fn visible_nodes(&self, nodes: &[CallTraceNode]) -> Vec<bool> {
+        let Some(FrameFilter(filter)) = &self.frame_filter else {
+            return vec![true; nodes.len()];
+        };
+        let mut visible = nodes.iter().map(|node| filter(node)).collect::<Vec<_>>();
+        // children are always recorded after their parent
+        for node in nodes.iter().rev() {
+            if visible[node.idx] {
+                if let Some(parent) = node.parent {
+                    visible[parent] = true;
+                }
+            }
+        }
+        visible
+    }
+
     /// Writes a single item of a single node to the writer. Returns the index of the next item to
     /// be written.
     ///
@@ -195,7 +313,13 @@ impl<W: Write> TraceWriter<W> {
                 Ok(item_idx + 1)
             }
             TraceMemberOrder::Call(index) => {
-                self.write_node(nodes, node.children[*index])?;
+                let child = node.children[*index];
+                if self.visible.get(child).copied().unwrap_or(true) {
+                    self.write_node(nodes, child)?;
+                } else {
+                    self.write_branch()?;
+                    writeln!(self.writer, "{ELLIPSIS}")?;
+                }
                 Ok(item_idx + 1)
             }
             TraceMemberOrder::Step(index) => self.write_step(nodes, node_idx, item_idx, *index),
@@ -247,6 +371,9 @@ impl<W: Write> TraceWriter<W> {
         // Write return data.
         self.write_edge()?;
         self.write_trace_footer(&node.trace)?;
+        if self.config.bubble_revert_reasons {
+            self.write_revert_origin(nodes, idx)?;
+        }
         self.writer.write_all(b"\n")?;
 
         self.indentation_level -= 1;
@@ -278,10 +405,10 @@ impl<W: Write> TraceWriter<W> {
                 }
                 None => {
                     if trace.data.len() < 4 {
-                        ("fallback".to_string(), hex::encode(&trace.data))
+                        ("fallback".to_string(), self.fmt_bytes(&trace.data))
                     } else {
                         let (selector, data) = trace.data.split_at(4);
-                        (hex::encode(selector), hex::encode(data))
+                        (hex::encode(selector), self.fmt_bytes(data))
                     }
                 }
             };
@@ -298,18 +425,19 @@ impl<W: Write> TraceWriter<W> {
             }
 
             write!(self.writer, "({inputs})")?;
+        }
 
-            let action = match trace.kind {
-                CallKind::Call => None,
-                CallKind::StaticCall => Some(" [staticcall]"),
-                CallKind::CallCode => Some(" [callcode]"),
-                CallKind::DelegateCall => Some(" [delegatecall]"),
-                CallKind::AuthCall => Some(" [authcall]"),
-                CallKind::Create | CallKind::Create2 => unreachable!(),
-            };
-            if let Some(action) = action {
-                write!(self.writer, "{trace_kind_style}{action}{trace_kind_style:#}")?;
-            }
+        // regular calls and creations are the default and not labeled
+        let action = match trace.kind {
+            CallKind::Call | CallKind::Create => None,
+            CallKind::StaticCall => Some(" [staticcall]"),
+            CallKind::CallCode => Some(" [callcode]"),
+            CallKind::DelegateCall => Some(" [delegatecall]"),
+            CallKind::AuthCall => Some(" [authcall]"),
+            CallKind::Create2 => Some(" [create2]"),
+        };
+        if let Some(action) = action {
+            write!(self.writer, "{trace_kind_style}{action}{trace_kind_style:#}")?;
         }
 
         Ok(())
@@ -346,8 +474,8 @@ impl<W: Write> TraceWriter<W> {
             }
             writeln!(
                 self.writer,
-                "          data: {log_style}{data}{log_style:#}",
-                data = log.raw_log.data
+                "          data: {log_style}0x{data}{log_style:#}",
+                data = self.fmt_bytes(&log.raw_log.data)
             )?;
         }
 
@@ -432,12 +560,35 @@ impl<W: Write> TraceWriter<W> {
         {
             write!(self.writer, " {} bytes of code", trace.output.len())?;
         } else if !trace.output.is_empty() {
-            write!(self.writer, " {}", trace.output)?;
+            write!(self.writer, " 0x{}", self.fmt_bytes(&trace.output))?;
         }
 
         Ok(())
     }
 
+    /// Writes the revert reason and address of the call that a reverted call forwarded its revert
+    /// from, if any.
+    fn write_revert_origin(&mut self, nodes: &[CallTraceNode], idx: usize) -> io::Result<()> {
+        let Some(origin) = revert_origin(nodes, idx) else { return Ok(()) };
+        let Some(reason) = maybe_revert_reason(&origin.trace.output) else { return Ok(()) };
+        write!(
+            self.writer,
+            " {style}{RETURN}revert: {reason:?} (from {address}){style:#}",
+            style = self.trace_style(&nodes[idx].trace),
+            address = origin.trace.address.to_checksum_buffer(None),
+        )
+    }
+
+    /// Hex encodes the given data, truncated to the configured maximum number of bytes.
+    fn fmt_bytes(&self, data: &[u8]) -> String {
+        match self.config.max_bytes {
+            Some(max) if data.len() > max => {
+                format!("{}…(+{} bytes)", hex::encode(&data[..max]), data.len() - max)
+            }
+            _ => hex::encode(data),
+        }
+    }
+
     fn write_indentation(&mut self) -> io::Result<()> {
         self.writer.write_all(b"  ")?;
         for _ in 1..self.indentation_level {
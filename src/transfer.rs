@@ -61,6 +61,13 @@ impl TransferInspector {
         &self.transfers
     }
 
+    /// Clears all collected transfers, so the inspector can be reused for the next transaction.
+    ///
+    /// The configuration of the inspector is kept.
+    pub fn reset(&mut self) {
+        self.transfers.clear();
+    }
+
     /// Returns an iterator over the collected transfers.
     pub fn iter(&self) -> impl Iterator<Item = &TransferOperation> {
         self.transfers.iter()
@@ -0,0 +1,40 @@
+//! Creation inspector tests
+
+use crate::utils::{call_tx, contract_context};
+use alloy_primitives::{address, hex};
+use revm::{InspectEvm, MainBuilder};
+use revm_inspectors::creation::CreationInspector;
+
+#[test]
+fn test_nested_creations() {
+    /*
+    The factory creates a contract with the init code of the parent:
+
+    PUSH16 <parent init code> PUSH0 MSTORE PUSH1 0x10 PUSH1 0x10 PUSH0 CREATE POP STOP
+
+    The constructor of the parent creates the child and deploys an empty contract:
+
+    PUSH3 <child init code> PUSH0 MSTORE PUSH1 0x03 PUSH1 0x1d PUSH0 CREATE POP
+    PUSH0 PUSH0 RETURN
+
+    The constructor of the child deploys an empty contract:
+
+    PUSH0 PUSH0 RETURN
+    */
+    let code = hex!("6f625f5ff35f526003601d5ff0505f5ff35f52601060105ff05000");
+    let factory = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(factory, &code)]);
+
+    let mut insp = CreationInspector::default();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(factory)).unwrap();
+    assert!(res.result.is_success());
+
+    let parent = factory.create(0);
+    // new contracts start with a nonce of 1
+    let child = parent.create(1);
+    assert_eq!(insp.max_create_depth(), 2);
+    assert_eq!(insp.creation_chains(), [vec![factory, parent, child], vec![factory, parent]]);
+}
@@ -0,0 +1,65 @@
+//! EIP-2929 access record tests
+
+use crate::utils::{call_tx, contract_context};
+use alloy_primitives::{address, hex, U256};
+use revm::{bytecode::opcode, InspectEvm, MainBuilder};
+use revm_inspectors::eip2929::AccessRecordInspector;
+
+#[test]
+fn test_cold_then_warm_sload() {
+    /*
+    Loads storage slot 0 twice:
+
+    PUSH0 SLOAD POP PUSH0 SLOAD POP STOP
+    */
+    let code = hex!("5f54505f545000");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut insp = AccessRecordInspector::default();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let records = insp.access_records();
+    assert_eq!(records.len(), 2);
+    for record in records {
+        assert_eq!(record.opcode.get(), opcode::SLOAD);
+        assert_eq!(record.address, addr);
+        assert_eq!(record.slot, Some(U256::ZERO));
+    }
+    assert_eq!((records[0].step, records[1].step), (1, 4));
+    assert!(records[0].cold);
+    assert!(!records[1].cold);
+}
+
+#[test]
+fn test_selfdestruct_beneficiary() {
+    /*
+    Checks the balance of the beneficiary and destroys itself in its favor:
+
+    PUSH20 <beneficiary> BALANCE POP PUSH20 <beneficiary> SELFDESTRUCT
+    */
+    let code = hex!("7320000000000000000000000000000000000000023150732000000000000000000000000000000000000002ff");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+    let beneficiary = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut insp = AccessRecordInspector::default();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let records = insp.access_records();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].opcode.get(), opcode::BALANCE);
+    assert!(records[0].cold);
+    assert_eq!(records[1].opcode.get(), opcode::SELFDESTRUCT);
+    assert_eq!(records[1].address, beneficiary);
+    assert_eq!(records[1].step, 4);
+    assert!(!records[1].cold);
+}
@@ -0,0 +1,643 @@
+//! ERC-7562 validation tests
+
+use crate::utils::{call_tx, contract_context};
+use alloy_primitives::{
+    address, b256, hex, keccak256,
+    map::{HashMap, HashSet},
+    Address, Bytes, B256, U256,
+};
+use alloy_rpc_types_trace::geth::{erc7562::Erc7562Config, CallConfig};
+use revm::{
+    bytecode::{opcode, OpCode},
+    context::{BlockEnv, TxEnv},
+    context_interface::block::BlobExcessGasAndPrice,
+    context_interface::TransactTo,
+    database::CacheDB,
+    database_interface::EmptyDB,
+    interpreter::InstructionResult,
+    primitives::{eip4844::BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN, KECCAK_EMPTY},
+    state::AccountInfo,
+    InspectEvm, MainBuilder,
+};
+use revm_inspectors::tracing::{
+    erc7562::{
+        into_call_frame, to_sorted_json, used_opcodes_named, Erc7562Violation, StorageOp,
+        TransientStorageRead,
+    },
+    CallTraceArena, GethTraceBuilder, OpcodeFilter, TracingInspector, TracingInspectorConfig,
+};
+
+/// Executes a call to the first of the given contracts and returns the recorded traces.
+fn trace_call(contracts: &[(Address, &[u8])]) -> CallTraceArena {
+    let context = contract_context(contracts);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(
+        &Erc7562Config::default(),
+    ));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    evm.inspect_tx(call_tx(contracts[0].0)).unwrap();
+
+    insp.into_traces()
+}
+
+#[test]
+fn test_opcode_allowlist() {
+    /*
+    Caller calls the callee:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+
+    Callee selfdestructs:
+
+    PUSH0 SELFDESTRUCT
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let traces = trace_call(&[
+        (caller, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (callee, &hex!("5fff")),
+    ]);
+
+    let mut allowed = HashSet::from_iter([
+        opcode::PUSH0,
+        opcode::PUSH20,
+        opcode::GAS,
+        opcode::CALL,
+        opcode::POP,
+        opcode::STOP,
+    ]);
+    assert_eq!(
+        traces.validate_against_allowlist(&allowed),
+        Err(Erc7562Violation { opcode: opcode::SELFDESTRUCT, frame: 1, depth: 1, address: callee })
+    );
+
+    allowed.insert(opcode::SELFDESTRUCT);
+    assert_eq!(traces.validate_against_allowlist(&allowed), Ok(()));
+}
+
+#[test]
+fn test_recursion() {
+    /*
+    A calls B, unless it was called by B:
+
+    CALLER PUSH20 <b> EQ PUSH2 @end JUMPI
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP
+    end: JUMPDEST STOP
+
+    B calls A:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <a> GAS CALL POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let traces = trace_call(&[
+        (a, &hex!("3373200000000000000000000000000000000000000214610038575f5f5f5f5f7320000000000000000000000000000000000000025af1505b00")),
+        (b, &hex!("5f5f5f5f5f7310000000000000000000000000000000000000015af15000")),
+    ]);
+
+    assert_eq!(traces.nodes().len(), 3);
+    assert!(traces.has_recursion());
+    assert_eq!(traces.recursion_paths(), vec![vec![a, b, a]]);
+}
+
+#[test]
+fn test_delegatecall_is_no_recursion() {
+    /*
+    A delegatecalls the library:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <lib> GAS DELEGATECALL POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let lib = address!("0x3000000000000000000000000000000000000003");
+    let traces = trace_call(&[
+        (a, &hex!("5f5f5f5f7330000000000000000000000000000000000000035af45000")),
+        (lib, &hex!("00")),
+    ]);
+
+    assert_eq!(traces.nodes().len(), 2);
+    assert!(!traces.has_recursion());
+    assert!(traces.recursion_paths().is_empty());
+}
+
+#[test]
+fn test_block_dependent_opcodes() {
+    /*
+    Reads the block timestamp and number:
+
+    TIMESTAMP POP NUMBER POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let traces = trace_call(&[(a, &hex!("4250435000"))]);
+
+    assert_eq!(traces.block_dependent_opcodes(), vec![OpCode::NUMBER, OpCode::TIMESTAMP]);
+
+    let traces = trace_call(&[(a, &hex!("00"))]);
+    assert!(traces.block_dependent_opcodes().is_empty());
+}
+
+#[test]
+fn test_transient_reads() {
+    /*
+    Writes and reads a transient slot, then reads an unset transient slot:
+
+    PUSH1 0x2a PUSH1 0x01 TSTORE PUSH1 0x01 TLOAD POP PUSH1 0x02 TLOAD POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let traces = trace_call(&[(a, &hex!("602a60015d60015c5060025c5000"))]);
+
+    assert_eq!(
+        traces.transient_reads(),
+        vec![
+            TransientStorageRead {
+                frame: 0,
+                address: a,
+                slot: U256::from(1),
+                value: U256::from(42)
+            },
+            TransientStorageRead { frame: 0, address: a, slot: U256::from(2), value: U256::ZERO },
+        ]
+    );
+}
+
+#[test]
+fn test_filtered_storage_reads() {
+    /*
+    Writes and reads a storage and a transient slot:
+
+    PUSH1 0x2a PUSH1 0x01 SSTORE PUSH1 0x01 SLOAD POP
+    PUSH1 0x07 PUSH1 0x02 TSTORE PUSH1 0x02 TLOAD POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let context = contract_context(&[(a, &hex!("602a60015560015450600760025d60025c5000"))]);
+
+    // only the loads are recorded, not the steps that follow them
+    let filter = OpcodeFilter::new().enabled(OpCode::SLOAD).enabled(OpCode::TLOAD);
+    let mut insp = TracingInspector::new(
+        TracingInspectorConfig::from_geth_erc7562_config(&Erc7562Config::default())
+            .with_opcodes_filter(filter),
+    );
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let traces = insp.traces();
+    assert_eq!(traces.nodes()[0].trace.steps.len(), 2);
+    assert_eq!(
+        traces.transient_reads(),
+        vec![TransientStorageRead {
+            frame: 0,
+            address: a,
+            slot: U256::from(2),
+            value: U256::from(7)
+        }]
+    );
+    let ops = traces.storage_ops().into_iter().map(|op| (op.op, op.value)).collect::<Vec<_>>();
+    assert_eq!(ops, vec![(OpCode::SLOAD, U256::from(0x2a)), (OpCode::TLOAD, U256::from(7))]);
+}
+
+#[test]
+fn test_transient_slots_touched() {
+    /*
+    Caller writes transient slot 1 and calls the callee:
+
+    PUSH1 0x2a PUSH1 0x01 TSTORE
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+
+    Callee reads transient slot 2:
+
+    PUSH1 0x02 TLOAD POP STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let traces = trace_call(&[
+        (caller, &hex!("602a60015d5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (callee, &hex!("60025c5000")),
+    ]);
+
+    let slots = traces.transient_slots_touched();
+    assert_eq!(slots.len(), 2);
+    assert_eq!(slots[&caller], HashSet::from_iter([U256::from(1)]));
+    assert_eq!(slots[&callee], HashSet::from_iter([U256::from(2)]));
+}
+
+#[test]
+fn test_blob_accesses() {
+    /*
+    Reads the first blob hash and the blob base fee:
+
+    PUSH0 BLOBHASH POP BLOBBASEFEE POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let caller = address!("0x283b5b7d75e3e6b84b8e2161e8a468d733bbbe8d");
+    let blob_hash = b256!("0x01af2fd94f17364bc8ef371c4c90c3a33855ff972d10b9c03d0445b3fca063ea");
+
+    let blob_price = BlobExcessGasAndPrice::new(0, BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN);
+    let context = contract_context(&[(a, &hex!("5f49504a5000"))])
+        .modify_db_chained(|db| {
+            db.insert_account_info(
+                caller,
+                AccountInfo { balance: U256::from(u64::MAX), ..Default::default() },
+            );
+        })
+        .modify_block_chained(|block: &mut BlockEnv| {
+            block.blob_excess_gas_and_price = Some(blob_price);
+        });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(
+        &Erc7562Config::default(),
+    ));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm
+        .inspect_tx(TxEnv {
+            tx_type: 3,
+            caller,
+            gas_limit: 1000000,
+            kind: TransactTo::Call(a),
+            blob_hashes: vec![blob_hash],
+            max_fee_per_blob_gas: 1000000000,
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    let traces = insp.into_traces();
+    assert_eq!(traces.blob_accesses(), vec![(0, blob_hash)]);
+    assert_eq!(traces.blob_base_fee_reads(), vec![U256::from(blob_price.blob_gasprice)]);
+}
+
+#[test]
+fn test_call_failing_before_execution() {
+    /*
+    Caller calls the callee with a value it doesn't have, then calls it again without value:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH1 0x01 PUSH20 <callee> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let traces = trace_call(&[
+        (caller, &hex!("5f5f5f5f60017320000000000000000000000000000000000000025af1505f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (callee, &hex!("00")),
+    ]);
+
+    // the failed call ends before any step is executed without unbalancing the frames
+    let nodes = traces.nodes();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(nodes[0].children, vec![1, 2]);
+    assert_eq!(nodes[1].trace.status, Some(InstructionResult::OutOfFunds));
+    assert!(nodes[1].trace.steps.is_empty());
+    assert_eq!(nodes[2].trace.status, Some(InstructionResult::Stop));
+    assert!(nodes[1..].iter().all(|node| node.trace.depth == 1 && node.parent == Some(0)));
+
+    let frame = GethTraceBuilder::new(traces.into_nodes()).geth_erc7562_traces(
+        Erc7562Config::default(),
+        0,
+        CacheDB::<EmptyDB>::default(),
+    );
+    assert_eq!(frame.calls.len(), 2);
+    assert!(frame.calls[0].used_opcodes.is_empty());
+    assert_eq!(frame.calls[1].used_opcodes, [(opcode::STOP, 1)].into_iter().collect());
+}
+
+#[test]
+fn test_shared_slot_accesses() {
+    /*
+    Caller calls X, reads its own slot 5 and calls X again:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <x> GAS CALL POP
+    PUSH1 0x05 SLOAD POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <x> GAS CALL POP STOP
+
+    X reads slot 5:
+
+    PUSH1 0x05 SLOAD POP STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let x = address!("0x2000000000000000000000000000000000000002");
+    let traces = trace_call(&[
+        (caller, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af150600554505f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (x, &hex!("6005545000")),
+    ]);
+
+    assert_eq!(traces.shared_slot_accesses(), vec![(x, U256::from(5), vec![1, 2])]);
+}
+
+#[test]
+fn test_ext_code_hashes() {
+    /*
+    Reads the code hash of an EOA, itself and an account that doesn't exist:
+
+    PUSH20 <eoa> EXTCODEHASH POP ADDRESS EXTCODEHASH POP PUSH20 <missing> EXTCODEHASH POP STOP
+    */
+    let code = hex!("7330000000000000000000000000000000000000033f50303f507340000000000000000000000000000000000000043f5000");
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let eoa = address!("0x3000000000000000000000000000000000000003");
+    let missing = address!("0x4000000000000000000000000000000000000004");
+
+    let context = contract_context(&[(a, &code)]).modify_db_chained(|db| {
+        db.insert_account_info(eoa, AccountInfo { balance: U256::from(1), ..Default::default() });
+    });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(
+        &Erc7562Config::default(),
+    ));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let hashes = insp.traces().ext_code_hashes();
+    assert_eq!(hashes.len(), 1);
+    assert_eq!(
+        hashes[0],
+        HashMap::from_iter([(eoa, KECCAK_EMPTY), (a, keccak256(code)), (missing, B256::ZERO)])
+    );
+}
+
+#[test]
+fn test_used_opcodes_named() {
+    let used_opcodes = HashMap::from_iter([(opcode::SSTORE, 3), (0x0c, 1)]);
+    let named = used_opcodes_named(&used_opcodes);
+    assert_eq!(named, HashMap::from_iter([("SSTORE".to_string(), 3), ("0x0c".to_string(), 1)]));
+}
+
+#[test]
+fn test_delegatecall_storage_slots() {
+    /*
+    A delegatecalls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS DELEGATECALL POP STOP
+
+    B writes slot 7:
+
+    PUSH1 0x2a PUSH1 0x07 SSTORE STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let traces = trace_call(&[
+        (a, &hex!("5f5f5f5f7320000000000000000000000000000000000000025af45000")),
+        (b, &hex!("602a60075500")),
+    ]);
+
+    let delegate = &traces.nodes()[1];
+    assert_eq!(delegate.code_address(), b);
+    assert_eq!(delegate.execution_address(), a);
+    assert_eq!(
+        traces.storage_slots_touched(),
+        HashMap::from_iter([(a, HashSet::from_iter([U256::from(7)]))])
+    );
+}
+
+#[test]
+fn test_into_call_frame() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B returns a word:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let traces = trace_call(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("602a5f5260205ff3")),
+    ]);
+
+    let builder = GethTraceBuilder::new(traces.into_nodes());
+    let frame =
+        builder.geth_erc7562_traces(Erc7562Config::default(), 0, CacheDB::<EmptyDB>::default());
+    let call_frame = into_call_frame(frame);
+    assert_eq!(call_frame.calls.len(), 1);
+    assert_eq!(call_frame.calls[0].typ, "CALL");
+    assert_eq!(call_frame.calls[0].output, Some(B256::with_last_byte(0x2a).into()));
+    assert_eq!(call_frame, builder.geth_call_traces(CallConfig::default(), 0));
+}
+
+#[test]
+fn test_sorted_json() {
+    /*
+    Writes three slots and reads another:
+
+    PUSH1 0x01 PUSH1 0x01 SSTORE PUSH1 0x02 PUSH1 0x02 SSTORE PUSH1 0x03 PUSH1 0x03 SSTORE
+    PUSH1 0x04 SLOAD POP STOP
+    */
+    let addr = address!("0x1000000000000000000000000000000000000001");
+    let traces = trace_call(&[(addr, &hex!("6001600155600260025560036003556004545000"))]);
+
+    let builder = GethTraceBuilder::new(traces.into_nodes());
+    let json = || {
+        let frame =
+            builder.geth_erc7562_traces(Erc7562Config::default(), 0, CacheDB::<EmptyDB>::default());
+        serde_json::to_string(&to_sorted_json(&frame)).unwrap()
+    };
+    let output = json();
+    assert_eq!(output, json());
+
+    let writes = format!(
+        r#""writes":{{"{}":1,"{}":1,"{}":1}}"#,
+        B256::with_last_byte(1),
+        B256::with_last_byte(2),
+        B256::with_last_byte(3)
+    );
+    assert!(output.contains(&writes), "{output}");
+}
+
+#[test]
+fn test_invalid_opcodes() {
+    /*
+    Caller calls both callees:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <undefined> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <invalid> GAS CALL POP STOP
+
+    The first callee executes the undefined opcode 0x0c, the second one INVALID.
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let undefined = address!("0x2000000000000000000000000000000000000002");
+    let invalid = address!("0x3000000000000000000000000000000000000003");
+    let traces = trace_call(&[
+        (caller, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1505f5f5f5f5f7330000000000000000000000000000000000000035af15000")),
+        (undefined, &hex!("0c")),
+        (invalid, &hex!("fe")),
+    ]);
+
+    let nodes = traces.nodes();
+    assert_eq!(nodes[0].trace.status, Some(InstructionResult::Stop));
+    assert_eq!(nodes[1].trace.status, Some(InstructionResult::OpcodeNotFound));
+    assert_eq!(nodes[2].trace.status, Some(InstructionResult::InvalidFEOpcode));
+
+    let frame = GethTraceBuilder::new(traces.into_nodes()).geth_erc7562_traces(
+        Erc7562Config::default(),
+        0,
+        CacheDB::<EmptyDB>::default(),
+    );
+    assert_eq!(frame.calls.len(), 2);
+    assert_eq!(frame.calls[0].used_opcodes, HashMap::from_iter([(0x0c, 1)]));
+    assert_eq!(frame.calls[1].used_opcodes, HashMap::from_iter([(opcode::INVALID, 1)]));
+    assert_eq!(
+        used_opcodes_named(&frame.calls[0].used_opcodes),
+        HashMap::from_iter([("0x0c".to_string(), 1)])
+    );
+    assert_eq!(
+        used_opcodes_named(&frame.calls[1].used_opcodes),
+        HashMap::from_iter([("INVALID".to_string(), 1)])
+    );
+}
+
+#[test]
+fn test_keccak_preimage_limits() {
+    /*
+    The contract hashes 1 MB of memory, then the first word twice and the second word once:
+
+    PUSH1 0x01 PUSH0 MSTORE
+    PUSH3 0x100000 PUSH0 KECCAK256 POP
+    PUSH1 0x20 PUSH0 KECCAK256 POP
+    PUSH1 0x20 PUSH1 0x20 KECCAK256 POP
+    PUSH1 0x20 PUSH0 KECCAK256 POP STOP
+    */
+    let contract = address!("0x1000000000000000000000000000000000000001");
+    let context = contract_context(&[(
+        contract,
+        &hex!("60015f52621000005f205060205f205060206020205060205f205000"),
+    )]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(
+        &Erc7562Config::default(),
+    ));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(TxEnv { gas_limit: 5000000, ..call_tx(contract) }).unwrap();
+    assert!(res.result.is_success());
+
+    let builder = GethTraceBuilder::new(insp.into_traces().into_nodes());
+    let frame =
+        builder.geth_erc7562_traces(Erc7562Config::default(), 0, CacheDB::<EmptyDB>::default());
+    assert_eq!(frame.keccak.len(), 4);
+    assert_eq!(frame.keccak[0].len(), 0x100000);
+
+    let first_word = Bytes::from(B256::with_last_byte(1));
+    let builder = builder.with_max_keccak_preimage_len(Some(32)).with_max_keccak_preimages(Some(1));
+    let frame =
+        builder.geth_erc7562_traces(Erc7562Config::default(), 0, CacheDB::<EmptyDB>::default());
+    // the large preimage and the second distinct preimage are skipped, but still counted
+    assert_eq!(frame.keccak, vec![first_word.clone(), first_word]);
+    assert_eq!(frame.used_opcodes[&opcode::KECCAK256], 4);
+}
+
+#[test]
+fn test_storage_ops() {
+    /*
+    A stores a slot, calls B, loads the slot and stores and loads a transient slot:
+
+    PUSH1 0x2a PUSH1 0x01 SSTORE
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP
+    PUSH1 0x01 SLOAD POP
+    PUSH1 0x07 PUSH0 TSTORE PUSH0 TLOAD POP STOP
+
+    B stores a slot:
+
+    PUSH1 0x05 PUSH0 SSTORE STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let traces = trace_call(&[
+        (a, &hex!("602a6001555f5f5f5f5f7320000000000000000000000000000000000000025af1506001545060075f5d5f5c5000")),
+        (b, &hex!("60055f5500")),
+    ]);
+
+    let ops = traces
+        .storage_ops()
+        .into_iter()
+        .map(|op| (op.op, op.contract, op.slot, op.value, op.frame))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ops,
+        vec![
+            (OpCode::SSTORE, a, U256::from(1), U256::from(0x2a), 0),
+            (OpCode::SSTORE, b, U256::ZERO, U256::from(5), 1),
+            (OpCode::SLOAD, a, U256::from(1), U256::from(0x2a), 0),
+            (OpCode::TSTORE, a, U256::ZERO, U256::from(7), 0),
+            (OpCode::TLOAD, a, U256::ZERO, U256::from(7), 0),
+        ]
+    );
+
+    // the load follows `POP PUSH1 0x01` after the call
+    let call = traces.nodes()[0].trace.steps.iter().position(|step| step.op == OpCode::CALL);
+    assert_eq!(
+        traces.storage_ops()[2],
+        StorageOp {
+            op: OpCode::SLOAD,
+            contract: a,
+            slot: U256::from(1),
+            value: U256::from(0x2a),
+            frame: 0,
+            step: call.unwrap() + 3,
+        }
+    );
+}
+
+#[test]
+fn test_delegatecall_and_staticcall_value() {
+    /*
+    The wallet receives a value and delegatecalls the library:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <library> GAS DELEGATECALL POP STOP
+
+    The library staticcalls the oracle:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <oracle> GAS STATICCALL POP STOP
+
+    The oracle stops immediately:
+
+    STOP
+    */
+    let sender = address!("0x4000000000000000000000000000000000000004");
+    let wallet = address!("0x1000000000000000000000000000000000000001");
+    let library = address!("0x2000000000000000000000000000000000000002");
+    let oracle = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[
+        (wallet, &hex!("5f5f5f5f7320000000000000000000000000000000000000025af45000")),
+        (library, &hex!("5f5f5f5f7330000000000000000000000000000000000000035afa5000")),
+        (oracle, &hex!("00")),
+    ])
+    .modify_db_chained(|db| {
+        db.insert_account_info(
+            sender,
+            AccountInfo { balance: U256::from(100), ..Default::default() },
+        );
+    });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(
+        &Erc7562Config::default(),
+    ));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm
+        .inspect_tx(TxEnv {
+            caller: sender,
+            gas_limit: 1000000,
+            kind: TransactTo::Call(wallet),
+            value: U256::from(7),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    let frame = GethTraceBuilder::new(insp.into_traces().into_nodes()).geth_erc7562_traces(
+        Erc7562Config::default(),
+        0,
+        CacheDB::<EmptyDB>::default(),
+    );
+    assert_eq!(frame.value, Some(U256::from(7)));
+
+    // the delegatecall runs in the context of the wallet and inherits its value
+    let delegatecall = &frame.calls[0];
+    assert_eq!(delegatecall.to, Some(library));
+    assert_eq!(delegatecall.value, Some(U256::from(7)));
+
+    // a staticcall can't transfer any value
+    let staticcall = &delegatecall.calls[0];
+    assert_eq!(staticcall.to, Some(oracle));
+    assert_eq!(staticcall.value, Some(U256::ZERO));
+}
@@ -1,12 +1,17 @@
 //! Geth tests
-use crate::utils::deploy_contract;
-use alloy_primitives::{hex, map::HashMap, Address, Bytes, TxKind};
+use crate::utils::{call_tx, contract_context, deploy_contract};
+use alloy_primitives::{
+    address, hex, keccak256, map::HashMap, Address, Bytes, Selector, TxKind, B256, U256,
+};
 use alloy_rpc_types_eth::TransactionInfo;
 use alloy_rpc_types_trace::geth::{
-    mux::MuxConfig, CallConfig, FlatCallConfig, GethDebugBuiltInTracerType, GethDebugTracerConfig,
-    GethTrace, PreStateConfig, PreStateFrame,
+    erc7562::{CallFrameType, Erc7562Config},
+    mux::MuxConfig,
+    CallConfig, FlatCallConfig, FourByteFrame, GethDebugBuiltInTracerType, GethDebugTracerConfig,
+    GethDefaultTracingOptions, GethTrace, PreStateConfig, PreStateFrame, StructLog,
 };
 use revm::{
+    bytecode::opcode,
     context::TxEnv,
     context_interface::{ContextTr, TransactTo},
     database::CacheDB,
@@ -14,9 +19,13 @@ use revm::{
     handler::EvmTr,
     inspector::InspectorEvmTr,
     primitives::hardfork::SpecId,
-    Context, InspectEvm, MainBuilder, MainContext,
+    state::AccountInfo,
+    Context, DatabaseCommit, InspectEvm, MainBuilder, MainContext,
+};
+use revm_inspectors::tracing::{
+    geth::StackEncoding, types::CallKind, BlockSelectorStats, BlockTraceBuilder, FourByteInspector,
+    MuxError, MuxInspector, TracingInspector, TracingInspectorConfig,
 };
-use revm_inspectors::tracing::{MuxInspector, TracingInspector, TracingInspectorConfig};
 
 #[test]
 fn test_geth_calltracer_logs() {
@@ -504,3 +513,990 @@ fn test_geth_calltracer_nested_revert() {
     assert!(top_call.error.is_some(), "Top call should have an error");
     assert!(top_call.revert_reason.is_some(), "Top call should have a revert reason");
 }
+
+#[test]
+fn test_geth_create2_frame() {
+    /*
+    Factory deploying a contract via CREATE2 with salt 0x45:
+
+    PUSH18 <init code> PUSH0 MSTORE
+    PUSH1 0x45 PUSH1 18 PUSH1 14 PUSH0 CREATE2
+    PUSH0 MSTORE PUSH1 32 PUSH0 RETURN
+
+    The init code deploys `PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN`.
+    */
+    let code = hex!("716008600a5f3960085ff3602a5f5260205ff35f5260456012600e5ff55f5260205ff3");
+    let init_code = hex!("6008600a5f3960085ff3602a5f5260205ff3");
+    let factory = address!("0x1000000000000000000000000000000000000001");
+    let deployer = Address::ZERO;
+
+    let context = contract_context(&[(factory, &code)]);
+
+    let erc7562_config = Erc7562Config::default();
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(&erc7562_config));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm
+        .inspect_tx(TxEnv {
+            caller: deployer,
+            gas_limit: 1000000,
+            kind: TransactTo::Call(factory),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    let expected = factory.create2(B256::from(U256::from(0x45)), keccak256(init_code));
+
+    let frame = evm.inspector.geth_builder().geth_erc7562_traces(
+        erc7562_config,
+        res.result.gas_used(),
+        evm.ctx.db_ref(),
+    );
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1].kind(), CallKind::Create2);
+    assert_eq!(nodes[1].created_address(), Some(expected));
+    assert_eq!(nodes[1].deployed_code_size(), Some(8));
+    assert_eq!(nodes[0].created_address(), None);
+
+    let salt = nodes[1].trace.create2_salt.unwrap();
+    let init_code_hash = nodes[1].trace.init_code_hash.unwrap();
+    assert_eq!(salt, U256::from(0x45));
+    assert_eq!(init_code_hash, keccak256(init_code));
+    assert_eq!(factory.create2(B256::from(salt), init_code_hash), nodes[1].trace.address);
+    assert_eq!(nodes[0].trace.create2_salt, None);
+    assert_eq!(nodes[0].trace.init_code_hash, None);
+
+    assert_eq!(frame.calls.len(), 1);
+    assert_eq!(frame.calls[0].call_frame_type, CallFrameType::Create2);
+    assert_eq!(frame.calls[0].to, Some(expected));
+    assert_eq!(frame.calls[0].input, Bytes::from(init_code));
+}
+
+#[test]
+fn test_geth_trace_from_tracer_name() {
+    /*
+    Callee returns a single word:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+
+    Caller calls the callee with the selector 0xdeadbeef:
+
+    PUSH4 0xdeadbeef PUSH1 0xe0 SHL PUSH0 MSTORE
+    PUSH0 PUSH0 PUSH1 0x04 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+    */
+    let callee_code = hex!("602a5f5260205ff3");
+    let caller_code =
+        hex!("63deadbeef60e01b5f525f5f60045f5f7320000000000000000000000000000000000000025af15000");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(callee, &callee_code), (caller, &caller_code)]);
+
+    let trace = |name: &str, config: GethDebugTracerConfig| {
+        let mut insp = MuxInspector::try_from_tracer_name(name, config).unwrap();
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(caller)).unwrap();
+        assert!(res.result.is_success());
+
+        let (ctx, inspector) = evm.ctx_inspector();
+        inspector.try_into_geth_trace(&res, ctx.db_ref(), TransactionInfo::default()).unwrap()
+    };
+
+    let call_config = CallConfig { only_top_call: Some(false), with_log: Some(true) };
+    match trace("callTracer", GethDebugTracerConfig(serde_json::to_value(call_config).unwrap())) {
+        GethTrace::CallTracer(frame) => {
+            assert_eq!(frame.to, Some(caller));
+            assert_eq!(frame.calls.len(), 1);
+            assert_eq!(frame.calls[0].to, Some(callee));
+        }
+        _ => panic!("Expected CallTracer"),
+    }
+
+    match trace("4byteTracer", GethDebugTracerConfig::default()) {
+        GethTrace::FourByteTracer(frame) => {
+            assert_eq!(frame.0.len(), 1);
+            assert_eq!(frame.0["0xdeadbeef-0"], 1);
+        }
+        _ => panic!("Expected FourByteTracer"),
+    }
+
+    assert!(matches!(
+        MuxInspector::try_from_tracer_name("unknownTracer", GethDebugTracerConfig::default()),
+        Err(MuxError::UnknownTracer(name)) if name == "unknownTracer"
+    ));
+}
+
+#[test]
+fn test_mux_step_forwarding() {
+    /*
+    Caller calls the callee with the selector 0xdeadbeef:
+
+    PUSH4 0xdeadbeef PUSH1 0xe0 SHL PUSH0 MSTORE
+    PUSH0 PUSH0 PUSH1 0x04 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+
+    Callee does nothing:
+
+    STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (
+            caller,
+            &hex!("63deadbeef60e01b5f525f5f60045f5f7320000000000000000000000000000000000000025af15000"),
+        ),
+        (callee, &hex!("00")),
+    ]);
+
+    // the 4byte tracer only needs calls, the ERC-7562 tracer also needs the steps
+    let config = MuxConfig(HashMap::from_iter([
+        (GethDebugBuiltInTracerType::FourByteTracer, None),
+        (
+            GethDebugBuiltInTracerType::Erc7562Tracer,
+            Some(GethDebugTracerConfig(serde_json::to_value(Erc7562Config::default()).unwrap())),
+        ),
+    ]));
+    let mut insp = MuxInspector::try_from_config(config).unwrap();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let (ctx, inspector) = evm.ctx_inspector();
+    let mut frame =
+        inspector.try_into_mux_frame(&res, ctx.db_ref(), TransactionInfo::default()).unwrap();
+
+    let GethTrace::FourByteTracer(four_byte) =
+        frame.0.remove(&GethDebugBuiltInTracerType::FourByteTracer).unwrap()
+    else {
+        panic!("Expected FourByteTracer")
+    };
+    assert_eq!(four_byte.0.len(), 1);
+    assert_eq!(four_byte.0["0xdeadbeef-0"], 1);
+
+    let GethTrace::Erc7562Tracer(erc7562) =
+        frame.0.remove(&GethDebugBuiltInTracerType::Erc7562Tracer).unwrap()
+    else {
+        panic!("Expected Erc7562Tracer")
+    };
+    assert_eq!(erc7562.used_opcodes[&opcode::CALL], 1);
+    assert_eq!(erc7562.used_opcodes[&opcode::PUSH0], 5);
+    assert_eq!(erc7562.calls.len(), 1);
+    assert_eq!(erc7562.calls[0].used_opcodes, HashMap::from_iter([(opcode::STOP, 1)]));
+}
+
+#[test]
+fn test_tracer_config_unknown_fields() {
+    // clients may send fields of newer versions of a tracer config
+    let config = GethDebugTracerConfig(serde_json::json!({
+        "stackTopItemsSize": 3,
+        "futureFlag": true
+    }));
+    let erc7562_config: Erc7562Config = config.clone().from_value().unwrap();
+    assert_eq!(erc7562_config.stack_top_items_size, Some(3));
+    assert_eq!(erc7562_config.with_log, None);
+    assert!(erc7562_config.ignored_opcodes.is_empty());
+    assert!(MuxInspector::try_from_tracer_name("erc7562Tracer", config).is_ok());
+
+    let config = GethDebugTracerConfig(serde_json::json!({ "futureFlag": true }));
+    assert!(MuxInspector::try_from_tracer_name("callTracer", config).is_ok());
+
+    // known fields are still validated
+    let config = GethDebugTracerConfig(serde_json::json!({ "stackTopItemsSize": -1 }));
+    assert!(matches!(
+        MuxInspector::try_from_tracer_name("erc7562Tracer", config),
+        Err(MuxError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_geth_erc7562_value_transfer() {
+    let sender = address!("0x1000000000000000000000000000000000000001");
+    let receiver = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[]).modify_db_chained(|db| {
+        db.insert_account_info(
+            sender,
+            AccountInfo { balance: U256::from(u64::MAX), ..Default::default() },
+        );
+    });
+
+    let erc7562_config = Erc7562Config::default();
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(&erc7562_config));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm
+        .inspect_tx(TxEnv {
+            caller: sender,
+            gas_limit: 1000000,
+            kind: TransactTo::Call(receiver),
+            value: U256::from(1),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    let frame = evm.inspector.geth_builder().geth_erc7562_traces(
+        erc7562_config,
+        res.result.gas_used(),
+        evm.ctx.db_ref(),
+    );
+
+    assert_eq!(frame.from, sender);
+    assert_eq!(frame.to, Some(receiver));
+    assert_eq!(frame.value, Some(U256::from(1)));
+    assert_eq!(frame.gas_used, 21000);
+    assert!(frame.used_opcodes.is_empty());
+    assert!(frame.calls.is_empty());
+}
+
+#[test]
+fn test_geth_erc7562_frame_gas() {
+    /*
+    Callee returns a single word:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+
+    Caller forwards a capped amount of gas to the callee:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> PUSH2 0x1000 CALL POP STOP
+    */
+    let callee_code = hex!("602a5f5260205ff3");
+    let caller_code = hex!("5f5f5f5f5f732000000000000000000000000000000000000002611000f15000");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(callee, &callee_code), (caller, &caller_code)]);
+
+    let erc7562_config = Erc7562Config::default();
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(&erc7562_config));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let frame = evm.inspector.geth_builder().geth_erc7562_traces(
+        erc7562_config,
+        res.result.gas_used(),
+        evm.ctx.db_ref(),
+    );
+
+    // the top-level frame is limited to the gas left after the intrinsic cost
+    assert_eq!(frame.gas, 1000000 - 21000);
+    assert_eq!(frame.gas_used, res.result.gas_used());
+
+    assert_eq!(frame.calls.len(), 1);
+    assert_eq!(frame.calls[0].gas, 0x1000);
+    // PUSH1 + PUSH0 + MSTORE (incl. memory expansion) + PUSH1 + PUSH0 + RETURN
+    assert_eq!(frame.calls[0].gas_used, 16);
+}
+
+#[test]
+fn test_erc7562_captures_opcodes_and_storage() {
+    /*
+    Callee does nothing:
+
+    STOP
+
+    Caller writes and reads a storage slot, hashes a word and calls the callee:
+
+    PUSH1 0x2a PUSH0 SSTORE PUSH0 SLOAD POP PUSH1 0x20 PUSH0 KECCAK256 POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+    */
+    let callee_code = hex!("00");
+    let caller_code = hex!(
+        "602a5f555f545060205f20505f5f5f5f5f7320000000000000000000000000000000000000025af15000"
+    );
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(callee, &callee_code), (caller, &caller_code)]);
+
+    let erc7562_config = Erc7562Config::default();
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(&erc7562_config));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let frame = evm.inspector.geth_builder().geth_erc7562_traces(
+        erc7562_config,
+        res.result.gas_used(),
+        evm.ctx.db_ref(),
+    );
+
+    let expected_opcodes = HashMap::from_iter([
+        (opcode::PUSH1, 2),
+        (opcode::PUSH0, 8),
+        (opcode::PUSH20, 1),
+        (opcode::SSTORE, 1),
+        (opcode::SLOAD, 1),
+        (opcode::KECCAK256, 1),
+        (opcode::POP, 3),
+        (opcode::GAS, 1),
+        (opcode::CALL, 1),
+        (opcode::STOP, 1),
+    ]);
+    assert_eq!(frame.used_opcodes, expected_opcodes);
+    assert_eq!(frame.keccak, vec![Bytes::from(vec![0; 32])]);
+
+    // the slot is read after it was written, so only the write is recorded
+    assert_eq!(frame.accessed_slots.writes, HashMap::from_iter([(B256::ZERO, 1)]));
+    assert!(frame.accessed_slots.reads.is_empty());
+
+    assert_eq!(frame.calls.len(), 1);
+    assert_eq!(frame.calls[0].to, Some(callee));
+    assert_eq!(frame.calls[0].used_opcodes, HashMap::from_iter([(opcode::STOP, 1)]));
+}
+
+#[test]
+fn test_geth_struct_logs() {
+    /*
+    Stores a word in memory and storage and returns it:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x2a PUSH0 SSTORE PUSH1 0x20 PUSH0 RETURN
+    */
+    let code = hex!("602a5f52602a5f5560205ff3");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let opts = GethDefaultTracingOptions::default().enable_memory();
+    let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_config(&opts));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let frame = insp.geth_builder().geth_traces(
+        res.result.gas_used(),
+        res.result.output().unwrap_or_default().clone(),
+        opts,
+    );
+    assert!(!frame.failed);
+    assert_eq!(frame.struct_logs.len(), 9);
+
+    let expected = serde_json::json!([
+        {
+            "pc": 0,
+            "op": "PUSH1",
+            "gas": 979000,
+            "gasCost": 3,
+            "depth": 0,
+            "stack": [],
+            "memory": []
+        },
+        {
+            "pc": 2,
+            "op": "PUSH0",
+            "gas": 978997,
+            "gasCost": 2,
+            "depth": 0,
+            "stack": ["0x2a"],
+            "memory": []
+        },
+        {
+            "pc": 3,
+            "op": "MSTORE",
+            "gas": 978995,
+            "gasCost": 6,
+            "depth": 0,
+            "stack": ["0x2a", "0x0"],
+            "memory": []
+        },
+        {
+            "pc": 4,
+            "op": "PUSH1",
+            "gas": 978989,
+            "gasCost": 3,
+            "depth": 0,
+            "stack": [],
+            "memory": ["000000000000000000000000000000000000000000000000000000000000002a"]
+        }
+    ]);
+    assert_eq!(serde_json::to_value(&frame.struct_logs[..4]).unwrap(), expected);
+
+    let sstore = &frame.struct_logs[5];
+    assert_eq!(sstore.op, "SSTORE");
+    assert_eq!(sstore.storage.as_ref().unwrap()[&B256::ZERO], B256::from(U256::from(0x2a)));
+}
+
+#[test]
+fn test_geth_struct_logs_return_data() {
+    /*
+    Caller calls the callee and pops the result:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+
+    Callee returns a word:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (caller, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (callee, &hex!("602a5f5260205ff3")),
+    ]);
+
+    let trace = |opts: GethDefaultTracingOptions| {
+        let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_config(&opts));
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(caller)).unwrap();
+        assert!(res.result.is_success());
+        insp.geth_builder().geth_traces(res.result.gas_used(), Bytes::default(), opts)
+    };
+
+    let frame = trace(GethDefaultTracingOptions::default().enable_return_data());
+    let pop = frame.struct_logs.iter().rfind(|log| log.op == "POP").unwrap();
+    assert_eq!(pop.return_data, Some(U256::from(0x2a).to_be_bytes::<32>().into()));
+
+    let frame = trace(GethDefaultTracingOptions::default());
+    assert!(frame.struct_logs.iter().all(|log| log.return_data.is_none()));
+}
+
+#[test]
+fn test_geth_struct_logs_storage() {
+    /*
+    Writes two storage slots:
+
+    PUSH1 0x01 PUSH0 SSTORE PUSH1 0x02 PUSH1 0x01 SSTORE STOP
+    */
+    let code = hex!("60015f55600260015500");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let opts = GethDefaultTracingOptions::default();
+    let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_config(&opts));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let frame = insp.geth_builder().geth_traces(res.result.gas_used(), Bytes::default(), opts);
+
+    let storage = frame
+        .struct_logs
+        .iter()
+        .filter(|log| log.op == "SSTORE")
+        .map(|log| log.storage.clone().unwrap())
+        .collect::<Vec<_>>();
+    let slot = |slot: u64| B256::from(U256::from(slot));
+    assert_eq!(storage.len(), 2);
+    assert_eq!(storage[0], [(slot(0), slot(1))].into_iter().collect());
+    assert_eq!(storage[1], [(slot(0), slot(1)), (slot(1), slot(2))].into_iter().collect());
+
+    // only steps that access storage carry a snapshot
+    assert!(frame
+        .struct_logs
+        .iter()
+        .filter(|log| log.op != "SSTORE")
+        .all(|log| log.storage.is_none()));
+}
+
+#[test]
+fn test_geth_stack_encoding() {
+    let value = U256::from(1);
+    assert_eq!(
+        StackEncoding::FullHex.encode(value),
+        "0x0000000000000000000000000000000000000000000000000000000000000001"
+    );
+    assert_eq!(StackEncoding::MinimalHex.encode(value), "0x1");
+    assert_eq!(StackEncoding::Decimal.encode(value), "1");
+    assert_eq!(StackEncoding::default(), StackEncoding::FullHex);
+
+    let log = StructLog {
+        pc: 0,
+        op: "ADD".into(),
+        gas: 0,
+        gas_cost: 0,
+        depth: 1,
+        error: None,
+        stack: Some(vec![U256::from(255), U256::ZERO]),
+        return_data: None,
+        memory: None,
+        memory_size: None,
+        storage: None,
+        refund_counter: None,
+    };
+    assert_eq!(
+        StackEncoding::MinimalHex.encode_stack(&log),
+        Some(vec!["0xff".into(), "0x0".into()])
+    );
+    assert_eq!(StackEncoding::Decimal.encode_stack(&log), Some(vec!["255".into(), "0".into()]));
+}
+
+#[test]
+fn test_geth_traces_json_stack_encoding() {
+    /*
+    Pushes two numbers:
+
+    PUSH1 0x2a PUSH0 STOP
+    */
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &hex!("602a5f00"))]);
+
+    let opts = GethDefaultTracingOptions::default();
+    let mut insp = TracingInspector::new(TracingInspectorConfig::from_geth_config(&opts));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let stack = |encoding: StackEncoding| {
+        let trace = insp.geth_builder().with_stack_encoding(encoding).geth_traces_json(
+            res.result.gas_used(),
+            Bytes::default(),
+            opts,
+        );
+        trace["structLogs"][2]["stack"].clone()
+    };
+
+    assert_eq!(
+        stack(StackEncoding::FullHex),
+        serde_json::json!([
+            "0x000000000000000000000000000000000000000000000000000000000000002a",
+            "0x0000000000000000000000000000000000000000000000000000000000000000"
+        ])
+    );
+    assert_eq!(stack(StackEncoding::MinimalHex), serde_json::json!(["0x2a", "0x0"]));
+    assert_eq!(stack(StackEncoding::Decimal), serde_json::json!(["42", "0"]));
+}
+
+#[test]
+fn test_block_trace_builder() {
+    let sender = address!("0x1000000000000000000000000000000000000001");
+    let receiver = address!("0x2000000000000000000000000000000000000002");
+    let balance = U256::from(1_000_000_000_000_000_000u128);
+
+    let context = contract_context(&[]).modify_db_chained(|db| {
+        db.insert_account_info(sender, AccountInfo { balance, ..Default::default() });
+    });
+
+    let mut insp = MuxInspector::try_from_tracer_name(
+        "callTracer",
+        GethDebugTracerConfig(serde_json::to_value(CallConfig::default()).unwrap()),
+    )
+    .unwrap();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let mut builder = BlockTraceBuilder::new().with_state_diff();
+    let hashes = [B256::with_last_byte(1), B256::with_last_byte(2)];
+    for (index, hash) in hashes.into_iter().enumerate() {
+        let res = evm
+            .inspect_tx(TxEnv {
+                caller: sender,
+                gas_limit: 1000000,
+                kind: TransactTo::Call(receiver),
+                value: U256::from(index + 1),
+                nonce: index as u64,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(res.result.is_success());
+
+        let tx_info =
+            TransactionInfo { hash: Some(hash), index: Some(index as u64), ..Default::default() };
+        builder.push_transaction(evm.inspector, &res, evm.ctx.db_ref(), tx_info).unwrap();
+        evm.ctx.db_mut().commit(res.state);
+    }
+
+    let traces = builder.traces();
+    assert_eq!(traces.len(), 2);
+    for (index, (hash, trace)) in traces.iter().enumerate() {
+        assert_eq!(*hash, hashes[index]);
+        let GethTrace::CallTracer(frame) = trace else { panic!("expected call frame: {trace:?}") };
+        // the inspector was reset, so each trace only contains its own transaction
+        assert_eq!(frame.value, Some(U256::from(index + 1)));
+        assert!(frame.calls.is_empty());
+    }
+
+    let state_diff = builder.state_diff().unwrap();
+    assert_eq!(state_diff.pre[&sender].balance, Some(balance));
+    assert_eq!(state_diff.pre[&sender].nonce, None);
+    // the pre state of the receiver is from before the first transfer
+    assert_eq!(state_diff.pre[&receiver].balance, Some(U256::ZERO));
+    assert_eq!(state_diff.post[&sender].nonce, Some(2));
+    assert_eq!(state_diff.post[&receiver].balance, Some(U256::from(3)));
+}
+
+#[test]
+fn test_block_trace_builder_destroyed_account() {
+    /*
+    The first transaction deploys a contract with a balance that destroys itself when called:
+
+    PUSH2 0x5fff PUSH0 MSTORE PUSH1 0x02 PUSH1 0x1e RETURN
+
+    The runtime code is:
+
+    PUSH0 SELFDESTRUCT
+    */
+    let sender = address!("0x1000000000000000000000000000000000000001");
+    let contract = sender.create(0);
+    let balance = U256::from(1_000_000_000_000_000_000u128);
+
+    // before EIP-6780, so the contract is destroyed by a later transaction
+    let context = contract_context(&[])
+        .modify_db_chained(|db| {
+            db.insert_account_info(sender, AccountInfo { balance, ..Default::default() });
+        })
+        .modify_cfg_chained(|cfg| cfg.spec = SpecId::SHANGHAI);
+
+    let mut insp = MuxInspector::try_from_tracer_name(
+        "callTracer",
+        GethDebugTracerConfig(serde_json::to_value(CallConfig::default()).unwrap()),
+    )
+    .unwrap();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let mut builder = BlockTraceBuilder::new().with_state_diff();
+    let txs = [
+        TxEnv {
+            caller: sender,
+            gas_limit: 1000000,
+            kind: TransactTo::Create,
+            value: U256::from(1),
+            data: hex!("615fff5f526002601ef3").into(),
+            ..Default::default()
+        },
+        TxEnv {
+            caller: sender,
+            gas_limit: 1000000,
+            kind: TransactTo::Call(contract),
+            nonce: 1,
+            ..Default::default()
+        },
+    ];
+    for (index, tx) in txs.into_iter().enumerate() {
+        let res = evm.inspect_tx(tx).unwrap();
+        assert!(res.result.is_success());
+
+        let tx_info = TransactionInfo {
+            hash: Some(B256::with_last_byte(index as u8 + 1)),
+            index: Some(index as u64),
+            ..Default::default()
+        };
+        builder.push_transaction(evm.inspector, &res, evm.ctx.db_ref(), tx_info).unwrap();
+        evm.ctx.db_mut().commit(res.state);
+    }
+
+    // the contract neither existed before nor after the block
+    let state_diff = builder.state_diff().unwrap();
+    assert!(!state_diff.pre.contains_key(&contract));
+    assert!(!state_diff.post.contains_key(&contract));
+    assert_eq!(state_diff.pre[&sender].balance, Some(balance));
+    assert_eq!(state_diff.post[&sender].nonce, Some(2));
+}
+
+#[test]
+fn test_erc7562_gas_call_window() {
+    /*
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 GAS DUP1 CALL POP POP STOP
+    */
+    let code = hex!("5f5f5f5f5f5f5a80f1505000");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let erc7562_config = Erc7562Config::default();
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(&erc7562_config));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let gas_count = |window: usize| {
+        let frame = evm.inspector.geth_builder().with_gas_call_window(window).geth_erc7562_traces(
+            erc7562_config.clone(),
+            res.result.gas_used(),
+            evm.ctx.db_ref(),
+        );
+        frame.used_opcodes.get(&opcode::GAS).copied()
+    };
+
+    // the CALL is two opcodes after the GAS
+    assert_eq!(gas_count(0), Some(1));
+    assert_eq!(gas_count(1), Some(1));
+    assert_eq!(gas_count(2), None);
+}
+
+#[test]
+fn test_mux_inspector_reset() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B does nothing:
+
+    STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("00")),
+    ]);
+
+    let config = MuxConfig(HashMap::from_iter([
+        (GethDebugBuiltInTracerType::FourByteTracer, None),
+        (
+            GethDebugBuiltInTracerType::CallTracer,
+            Some(GethDebugTracerConfig(serde_json::to_value(CallConfig::default()).unwrap())),
+        ),
+    ]));
+    let mut insp = MuxInspector::try_from_config(config).unwrap();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let mut frames = Vec::new();
+    for (nonce, (to, data)) in
+        [(a, hex!("11111111")), (b, hex!("22222222"))].into_iter().enumerate()
+    {
+        let res = evm
+            .inspect_tx(TxEnv { data: data.into(), nonce: nonce as u64, ..call_tx(to) })
+            .unwrap();
+        assert!(res.result.is_success());
+
+        let (ctx, inspector) = evm.ctx_inspector();
+        frames.push(
+            inspector.try_into_mux_frame(&res, ctx.db_ref(), TransactionInfo::default()).unwrap(),
+        );
+        evm.ctx.db_mut().commit(res.state);
+        evm.inspector.fuse();
+    }
+
+    let frame = &frames[1].0;
+    let GethTrace::FourByteTracer(four_byte) = &frame[&GethDebugBuiltInTracerType::FourByteTracer]
+    else {
+        panic!("expected four byte frame");
+    };
+    assert_eq!(four_byte.0.keys().collect::<Vec<_>>(), ["0x22222222-0"]);
+    let GethTrace::CallTracer(call) = &frame[&GethDebugBuiltInTracerType::CallTracer] else {
+        panic!("expected call frame");
+    };
+    assert_eq!(call.to, Some(b));
+    assert!(call.calls.is_empty());
+}
+
+#[test]
+fn test_erc7562_log_positions() {
+    /*
+    A emits a log, calls B and emits another log:
+
+    PUSH0 PUSH0 LOG0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP PUSH0 PUSH0 LOG0 STOP
+
+    B does nothing:
+
+    STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5fa05f5f5f5f5f7320000000000000000000000000000000000000025af1505f5fa000")),
+        (b, &hex!("00")),
+    ]);
+
+    let erc7562_config = Erc7562Config { with_log: Some(true), ..Default::default() };
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(&erc7562_config));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let frame = evm.inspector.geth_builder().geth_erc7562_traces(
+        erc7562_config,
+        res.result.gas_used(),
+        evm.ctx.db_ref(),
+    );
+
+    // like geth, the position is the number of subcalls made before the log
+    let logs = frame.logs;
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0].position, Some(0));
+    assert_eq!(logs[1].position, Some(1));
+    assert_eq!(frame.calls.len(), 1);
+}
+
+#[test]
+fn test_erc7562_log_topics() {
+    /*
+    PUSH1 0x03 PUSH1 0x02 PUSH1 0x01 PUSH0 PUSH0 LOG3 STOP
+    */
+    let code = hex!("6003600260015f5fa300");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let erc7562_config = Erc7562Config { with_log: Some(true), ..Default::default() };
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::from_geth_erc7562_config(&erc7562_config));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let frame = evm.inspector.geth_builder().geth_erc7562_traces(
+        erc7562_config,
+        res.result.gas_used(),
+        evm.ctx.db_ref(),
+    );
+
+    assert_eq!(frame.logs.len(), 1);
+    let log = &frame.logs[0];
+    assert_eq!(log.address, Some(addr));
+    assert_eq!(
+        log.topics.as_deref(),
+        Some(&[B256::with_last_byte(1), B256::with_last_byte(2), B256::with_last_byte(3)][..])
+    );
+    assert_eq!(log.data, Some(Bytes::new()));
+}
+
+#[test]
+fn test_call_tracer_preset() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B counts down from 10 in a loop and emits a log:
+
+    PUSH1 0x0a JUMPDEST PUSH1 0x01 SWAP1 SUB DUP1 PUSH1 0x02 JUMPI POP PUSH0 PUSH0 LOG0 STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("600a5b6001900380600257505f5fa000")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::call_tracer());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes.len(), 2);
+    assert!(nodes.iter().all(|node| node.trace.steps.is_empty()));
+
+    let frame = insp
+        .geth_builder()
+        .geth_call_traces(CallConfig::default().with_log(), res.result.gas_used());
+    assert_eq!(frame.calls.len(), 1);
+    assert_eq!(frame.calls[0].to, Some(b));
+    assert_eq!(frame.calls[0].logs.len(), 1);
+}
+
+#[test]
+fn test_call_tracer_with_log() {
+    /*
+    A emits a log and calls B, then stops or reverts:
+
+    PUSH0 PUSH0 LOG0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+    PUSH0 PUSH0 LOG0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP PUSH0 PUSH0 REVERT
+
+    B emits a log with a topic:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x01 PUSH1 0x20 PUSH0 LOG1 STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let trace = |a_code: &[u8]| {
+        let context = contract_context(&[(a, a_code), (b, &hex!("602a5f52600160205fa100"))]);
+
+        let call_config = CallConfig::default().with_log();
+        let mut insp =
+            TracingInspector::new(TracingInspectorConfig::from_geth_call_config(&call_config));
+        let mut evm = context.build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(a)).unwrap();
+        insp.geth_builder().geth_call_traces(call_config, res.result.gas_used())
+    };
+
+    let frame = trace(&hex!("5f5fa05f5f5f5f5f7320000000000000000000000000000000000000025af15000"));
+    assert_eq!(frame.logs.len(), 1);
+    assert_eq!(frame.logs[0].address, Some(a));
+    assert_eq!((frame.logs[0].position, frame.logs[0].index), (Some(0), Some(0)));
+
+    // the log of B is attached to the subcall
+    let log = &frame.calls[0].logs[..];
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].address, Some(b));
+    assert_eq!(log[0].topics, Some(vec![B256::with_last_byte(0x01)]));
+    assert_eq!(log[0].data, Some(B256::with_last_byte(0x2a).into()));
+    assert_eq!((log[0].position, log[0].index), (Some(0), Some(1)));
+
+    // all logs are discarded if the root call reverts
+    let frame =
+        trace(&hex!("5f5fa05f5f5f5f5f7320000000000000000000000000000000000000025af1505f5ffd"));
+    assert!(frame.error.is_some());
+    assert!(frame.logs.is_empty());
+    assert!(frame.calls[0].logs.is_empty());
+}
+
+#[test]
+fn test_block_selector_stats() {
+    /*
+    Callee stops immediately:
+
+    STOP
+
+    Caller calls the callee with the selector 0xdeadbeef:
+
+    PUSH4 0xdeadbeef PUSH1 0xe0 SHL PUSH0 MSTORE
+    PUSH0 PUSH0 PUSH1 0x04 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+    */
+    let callee_code = hex!("00");
+    let caller_code =
+        hex!("63deadbeef60e01b5f525f5f60045f5f7320000000000000000000000000000000000000025af15000");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(callee, &callee_code), (caller, &caller_code)]);
+
+    let selectors = |data: &[u8]| {
+        let mut insp = FourByteInspector::default();
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm
+            .inspect_tx(TxEnv { data: Bytes::copy_from_slice(data), ..call_tx(caller) })
+            .unwrap();
+        assert!(res.result.is_success());
+        insp.inner().clone()
+    };
+
+    let first = selectors(&hex!("deadbeef"));
+    let second = selectors(&hex!(
+        "12345678000000000000000000000000000000000000000000000000000000000000002a"
+    ));
+    assert_eq!(first[&(Selector::from(hex!("deadbeef")), 0)], 2);
+
+    let stats = BlockSelectorStats::from_transactions([&first, &second]);
+    assert_eq!(stats.counts().len(), 2);
+    assert_eq!(stats.total(), 4);
+    assert_eq!(stats.top(1), vec![((Selector::from(hex!("deadbeef")), 0), 3)]);
+    assert_eq!(
+        stats.top(5),
+        vec![
+            ((Selector::from(hex!("deadbeef")), 0), 3),
+            ((Selector::from(hex!("12345678")), 32), 1),
+        ]
+    );
+
+    let frame = FourByteFrame::from(&stats);
+    assert_eq!(frame.0["0xdeadbeef-0"], 3);
+    assert_eq!(frame.0["0x12345678-32"], 1);
+}
@@ -4,17 +4,33 @@ pub mod accesslist;
 #[cfg(feature = "std")]
 pub mod utils;
 
+#[cfg(feature = "std")]
+mod creation;
 #[cfg(feature = "std")]
 mod edge_cov;
 #[cfg(feature = "std")]
+mod eip2929;
+#[cfg(feature = "std")]
+mod erc7562;
+#[cfg(feature = "std")]
 mod geth;
 #[cfg(feature = "js-tracer")]
 mod geth_js;
 #[cfg(feature = "std")]
+mod memory_copy;
+#[cfg(feature = "std")]
+mod memory_expansion;
+#[cfg(feature = "std")]
+mod noop;
+#[cfg(feature = "std")]
 mod parity;
+#[cfg(feature = "std")]
+mod reentrancy;
 #[cfg(feature = "js-tracer")]
 mod test_native_bigint;
 #[cfg(feature = "std")]
+mod tracing;
+#[cfg(feature = "std")]
 mod transfer;
 #[cfg(feature = "std")]
 mod writer;
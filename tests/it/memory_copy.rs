@@ -0,0 +1,49 @@
+//! Memory copy inspector tests
+
+use crate::utils::{call_tx, contract_context};
+use alloy_primitives::{address, hex, U256};
+use revm::{bytecode::OpCode, InspectEvm, MainBuilder};
+use revm_inspectors::memory_copy::{MemoryCopy, MemoryCopyInspector};
+
+#[test]
+fn test_memory_copies() {
+    /*
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 PUSH1 0x10 MCOPY
+    PUSH1 0x04 PUSH1 0x01 PUSH1 0x40 CODECOPY STOP
+    */
+    let code = hex!("602a5f5260205f60105e6004600160403900");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut insp = MemoryCopyInspector::default();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let copies = insp.memory_copies();
+    assert_eq!(
+        copies,
+        [
+            MemoryCopy {
+                opcode: OpCode::MCOPY,
+                address: addr,
+                code_address: None,
+                dest_offset: U256::from(0x10),
+                src_offset: U256::ZERO,
+                len: U256::from(0x20),
+            },
+            MemoryCopy {
+                opcode: OpCode::CODECOPY,
+                address: addr,
+                code_address: None,
+                dest_offset: U256::from(0x40),
+                src_offset: U256::from(1),
+                len: U256::from(4),
+            },
+        ]
+    );
+    assert!(copies[0].is_overlapping());
+    assert!(!copies[1].is_overlapping());
+}
@@ -0,0 +1,52 @@
+//! Memory expansion inspector tests
+
+use crate::utils::{call_tx, contract_context};
+use alloy_primitives::{address, hex};
+use revm::{bytecode::OpCode, InspectEvm, MainBuilder};
+use revm_inspectors::memory_expansion::{MemoryExpansion, MemoryExpansionInspector};
+
+#[test]
+fn test_memory_expansion_gas() {
+    /*
+    PUSH1 0x2a PUSH2 0x1000 MSTORE PUSH1 0x20 PUSH0 PUSH2 0x2000 CALLDATACOPY
+    PUSH0 MLOAD POP STOP
+    */
+    let code = hex!("602a6110005260205f612000375f515000");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut insp = MemoryExpansionInspector::default();
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    // the memory of `n` words costs `3 * n + n * n / 512`
+    let memory_gas = |words: u64| 3 * words + words * words / 512;
+    assert_eq!(
+        insp.memory_expansions(),
+        [
+            MemoryExpansion {
+                opcode: OpCode::MSTORE,
+                address: addr,
+                pc: 5,
+                words_before: 0,
+                words_after: 129,
+                gas: memory_gas(129),
+            },
+            MemoryExpansion {
+                opcode: OpCode::CALLDATACOPY,
+                address: addr,
+                pc: 12,
+                words_before: 129,
+                words_after: 257,
+                gas: memory_gas(257) - memory_gas(129),
+            },
+        ]
+    );
+    assert_eq!(insp.memory_expansion_gas(), 900);
+
+    insp.reset();
+    assert!(insp.memory_expansions().is_empty());
+}
@@ -0,0 +1,30 @@
+//! Noop inspector tests
+
+use crate::utils::{call_tx, contract_context};
+use alloy_primitives::{address, hex};
+use revm::{ExecuteEvm, InspectEvm, MainBuilder};
+use revm_inspectors::NoOpInspector;
+
+#[test]
+fn test_noop_inspector_matches_execution() {
+    /*
+    Stores a word in memory and storage and returns it:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x2a PUSH0 SSTORE PUSH1 0x20 PUSH0 RETURN
+    */
+    let code = hex!("602a5f52602a5f5560205ff3");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+    let tx = call_tx(addr);
+
+    let expected = context.clone().build_mainnet().transact(tx.clone()).unwrap();
+    assert!(expected.result.is_success());
+
+    let mut evm = context.build_mainnet_with_inspector(NoOpInspector);
+    let res = evm.inspect_tx(tx).unwrap();
+
+    assert_eq!(res.result, expected.result);
+    assert_eq!(res.result.gas_used(), expected.result.gas_used());
+    assert_eq!(res.result.output(), expected.result.output());
+}
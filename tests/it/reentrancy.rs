@@ -0,0 +1,65 @@
+//! Reentrancy inspector tests
+
+use crate::utils::{call_tx, contract_context};
+use alloy_primitives::{address, hex};
+use revm::{InspectEvm, MainBuilder};
+use revm_inspectors::reentrancy::{ReentrancyEvent, ReentrancyInspector};
+
+#[test]
+fn test_reentrancy() {
+    /*
+    A calls B, unless it's called by B:
+
+    CALLER PUSH20 <b> EQ PUSH1 0x37 JUMPI PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP
+    JUMPDEST STOP
+
+    B calls back into A:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <a> GAS CALL POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[(
+                    a,
+                    &hex!("33732000000000000000000000000000000000000002146037575f5f5f5f5f7320000000000000000000000000000000000000025af1505b00")[..],
+                ),
+                (b, &hex!("5f5f5f5f5f7310000000000000000000000000000000000000015af15000")[..])]);
+
+    let mut insp = ReentrancyInspector::new(a);
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    assert_eq!(evm.inspector.reentrancies(), [ReentrancyEvent { depth: 2, caller: b }]);
+}
+
+#[test]
+fn test_reentrancy_from_constructor() {
+    /*
+    A deploys a contract, unless it's called by the contract:
+
+    CALLER PUSH1 0x2c JUMPI PUSH30 <init code> PUSH0 MSTORE
+    PUSH1 0x1e PUSH1 0x02 PUSH0 CREATE POP JUMPDEST STOP
+
+    The init code calls back into A:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <a> GAS CALL POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(
+        a,
+        &hex!("33602c577d5f5f5f5f5f7310000000000000000000000000000000000000015af150005f52601e60025ff0505b00"),
+    )]);
+
+    let mut insp = ReentrancyInspector::new(a);
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    // the constructor runs one frame below A
+    assert_eq!(evm.inspector.reentrancies(), [ReentrancyEvent { depth: 2, caller: a.create(0) }]);
+}
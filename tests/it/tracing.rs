@@ -0,0 +1,2144 @@
+//! Tracing inspector tests
+
+use crate::utils::{call_tx, contract_context, verify_deterministic};
+use alloy_json_abi::Function;
+use alloy_primitives::{
+    address, hex, keccak256,
+    map::{HashMap, HashSet},
+    Address, Bytes, B256, U256,
+};
+use alloy_rpc_types_trace::geth::CallConfig;
+use alloy_sol_types::{sol, Revert, SolCall, SolError};
+use revm::{
+    bytecode::{opcode, Bytecode, OpCode},
+    context::TxEnv,
+    context_interface::{ContextTr, TransactTo},
+    database::CacheDB,
+    database_interface::{DatabaseCommit, EmptyDB},
+    inspector::JournalExt,
+    interpreter::{
+        interpreter_types::Jumps, CallInputs, CallOutcome, CreateInputs, CreateOutcome,
+        InstructionResult, Interpreter,
+    },
+    primitives::{Log, KECCAK_EMPTY},
+    state::AccountInfo,
+    InspectEvm, Inspector, MainBuilder,
+};
+use revm_inspectors::tracing::{
+    predict_create2_address,
+    types::{DecodedCallData, PrecompileInput, SourceLocation, SourceMap, TraceError, TraceEvent},
+    CallTraceArena, DelegateGasAttribution, OpcodeFilter, TraceDiff, TracingInspector,
+    TracingInspectorConfig,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[test]
+fn test_returndata_snapshots() {
+    /*
+    Callee returns a single word:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+
+    Caller calls the callee and reads the returndata size:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP RETURNDATASIZE STOP
+    */
+    let callee_code = hex!("602a5f5260205ff3");
+    let caller_code = hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1503d00");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(callee, &callee_code), (caller, &caller_code)]);
+
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::default_geth().returndata_snapshots());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let steps = &insp.traces().nodes()[0].trace.steps;
+    let call = steps.iter().position(|step| step.op.get() == opcode::CALL).unwrap();
+    assert!(steps[..=call].iter().all(|step| step.returndata.is_empty()));
+
+    let returndatasize = steps.iter().find(|step| step.op.get() == opcode::RETURNDATASIZE).unwrap();
+    assert_eq!(returndatasize.returndata.len(), 32);
+    assert_eq!(returndatasize.returndata[31], 0x2a);
+}
+
+#[test]
+fn test_only_top_call() {
+    /*
+    Callee returns a single word:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+
+    Caller calls the callee twice:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+    */
+    let callee_code = hex!("602a5f5260205ff3");
+    let caller_code = hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1505f5f5f5f5f7320000000000000000000000000000000000000025af15000");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(callee, &callee_code), (caller, &caller_code)]);
+
+    let call_config = CallConfig { only_top_call: Some(true), with_log: Some(true) };
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::from_geth_call_config(&call_config).steps());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes.len(), 1);
+    assert!(nodes[0].children.is_empty());
+
+    // only the caller's own steps are recorded
+    let steps = &nodes[0].trace.steps;
+    assert_eq!(steps.iter().filter(|step| step.op.get() == opcode::CALL).count(), 2);
+    assert!(steps.iter().all(|step| step.op.get() != opcode::RETURN));
+
+    let frame = insp.geth_builder().geth_call_traces(call_config, res.result.gas_used());
+    assert!(frame.calls.is_empty());
+}
+
+#[test]
+fn test_sstore_clear_refund() {
+    /*
+    Clears storage slot 0:
+
+    PUSH0 PUSH0 SSTORE STOP
+    */
+    let code = hex!("5f5f5500");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]).modify_db_chained(|db| {
+        db.insert_account_storage(addr, U256::ZERO, U256::from(1)).unwrap();
+    });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    // SSTORE_CLEARS_SCHEDULE since EIP-3529
+    assert_eq!(insp.traces().total_refund(), 4800);
+    assert_eq!(insp.traces().nodes()[0].trace.refund, 4800);
+}
+
+#[test]
+fn test_exclude_precompile_calls() {
+    /*
+    Calls the ecrecover precompile:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH1 0x01 GAS CALL POP STOP
+    */
+    let code = hex!("5f5f5f5f5f60015af15000");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    for exclude in [false, true] {
+        let mut insp = TracingInspector::new(
+            TracingInspectorConfig::default_parity().set_exclude_precompile_calls(exclude),
+        );
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+
+        let res = evm.inspect_tx(call_tx(addr)).unwrap();
+        assert!(res.result.is_success());
+
+        let root = &insp.traces().nodes()[0];
+        assert_eq!(root.children.is_empty(), exclude);
+        if !exclude {
+            let child = &insp.traces().nodes()[root.children[0]];
+            assert_eq!(child.trace.address, Address::with_last_byte(1));
+        }
+    }
+}
+
+#[test]
+fn test_append_arena() {
+    /*
+    Callee emits an empty log:
+
+    PUSH0 PUSH0 LOG0 STOP
+
+    Caller calls the callee twice:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+    */
+    let callee_code = hex!("5f5fa000");
+    let caller_code = hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1505f5f5f5f5f7320000000000000000000000000000000000000025af15000");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(callee, &callee_code), (caller, &caller_code)]);
+
+    let trace = || {
+        let mut insp =
+            TracingInspector::new(TracingInspectorConfig::default_parity().record_logs());
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(caller)).unwrap();
+        assert!(res.result.is_success());
+        insp.into_traces()
+    };
+
+    let mut arena = trace();
+    arena.append(trace());
+
+    let nodes = arena.nodes();
+    assert_eq!(nodes.len(), 6);
+    assert_eq!(arena.roots().map(|node| node.idx).collect::<Vec<_>>(), vec![0, 3]);
+    for (idx, node) in nodes.iter().enumerate() {
+        assert_eq!(node.idx, idx);
+        for child in &node.children {
+            assert_eq!(nodes[*child].parent, Some(idx));
+        }
+    }
+    assert_eq!(nodes[3].children, vec![4, 5]);
+
+    let log_indices = nodes.iter().flat_map(|node| &node.logs).map(|log| log.index);
+    assert_eq!(log_indices.collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_invalid_jumps() {
+    /*
+    Jumps to a JUMPDEST, then to a STOP:
+
+    PUSH1 0x05 JUMP INVALID INVALID JUMPDEST PUSH1 0x0a JUMP JUMPDEST STOP
+    */
+    let code = hex!("600556fefe5b600a565b00");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(!res.result.is_success());
+
+    let node = &insp.traces().nodes()[0];
+    assert_eq!(node.invalid_jumps(), vec![(4, 0x0a)]);
+    assert_eq!(node.trace.steps[4].pc, 8);
+}
+
+#[test]
+fn test_decode_calls() {
+    /*
+    Calls the callee with `transfer(0x3000000000000000000000000000000000000003, 5)` and the same
+    arguments with an unknown selector:
+
+    PUSH4 0xa9059cbb PUSH0 MSTORE PUSH20 <recipient> PUSH1 0x20 MSTORE PUSH1 0x05 PUSH1 0x40 MSTORE
+    PUSH0 PUSH0 PUSH1 0x44 PUSH1 0x1c PUSH0 PUSH20 <callee> GAS CALL POP
+    PUSH4 0xdeadbeef PUSH0 MSTORE
+    PUSH0 PUSH0 PUSH1 0x44 PUSH1 0x1c PUSH0 PUSH20 <callee> GAS CALL POP STOP
+    */
+    let caller_code = hex!("63a9059cbb5f5273300000000000000000000000000000000000000360205260056040525f5f6044601c5f7320000000000000000000000000000000000000025af15063deadbeef5f525f5f6044601c5f7320000000000000000000000000000000000000025af15000");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(caller, &caller_code)]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let transfer = Function::parse("transfer(address,uint256)").unwrap();
+    let mut arena = insp.into_traces();
+    arena.decode_calls(|selector| (selector == transfer.selector()).then(|| transfer.clone()));
+
+    let nodes = arena.nodes();
+    assert_eq!(nodes.len(), 3);
+    assert!(nodes[1..].iter().all(|node| node.trace.address == callee));
+    assert_eq!(nodes[0].trace.decoded, None);
+    assert_eq!(
+        nodes[1].trace.decoded.as_ref().unwrap().call_data,
+        Some(DecodedCallData {
+            signature: "transfer(address,uint256)".to_string(),
+            args: vec!["0x3000000000000000000000000000000000000003".to_string(), "5".to_string()],
+        })
+    );
+    assert_eq!(nodes[2].trace.decoded, None);
+    assert_eq!(nodes[2].trace.data[..4], hex!("deadbeef"));
+}
+
+#[test]
+fn test_gas_by_address() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B delegatecalls the library:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <lib> GAS DELEGATECALL POP STOP
+
+    The library stores a word in memory:
+
+    PUSH1 0x2a PUSH0 MSTORE STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let lib = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("5f5f5f5f7330000000000000000000000000000000000000035af45000")),
+        (lib, &hex!("602a5f5200")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let arena = insp.traces();
+    let nodes = arena.nodes();
+    assert_eq!(nodes.len(), 3);
+    let self_gas = |idx: usize| {
+        let subcalls = nodes[idx].children.iter().map(|child| nodes[*child].trace.gas_used);
+        nodes[idx].trace.gas_used - subcalls.sum::<u64>()
+    };
+
+    let by_code = arena.gas_by_address();
+    assert_eq!(by_code.len(), 3);
+    assert_eq!(by_code[&a], self_gas(0));
+    assert_eq!(by_code[&b], self_gas(1));
+    assert_eq!(by_code[&lib], self_gas(2));
+    assert_eq!(by_code.values().sum::<u64>(), nodes[0].trace.gas_used);
+
+    let by_storage = arena.gas_by_address_with(DelegateGasAttribution::StorageAddress);
+    assert_eq!(by_storage.len(), 2);
+    assert_eq!(by_storage[&a], self_gas(0));
+    assert_eq!(by_storage[&b], self_gas(1) + self_gas(2));
+}
+
+#[test]
+fn test_on_step() {
+    /*
+    Takes a conditional jump, then doesn't take one:
+
+    PUSH1 0x01 PUSH1 0x07 JUMPI INVALID INVALID JUMPDEST PUSH0 PUSH1 0x00 JUMPI STOP
+    */
+    let code = hex!("6001600757fefe5b5f60005700");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let jumpis = Arc::new(AtomicUsize::new(0));
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth());
+    insp.on_step({
+        let jumpis = jumpis.clone();
+        move |step, interp| {
+            if step.op.get() == opcode::JUMPI {
+                // the step is complete once the callback is invoked
+                assert_eq!(step.gas_cost, 10);
+                assert_eq!(interp.bytecode.pc(), if step.pc == 4 { 7 } else { 12 });
+                jumpis.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    });
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+    assert_eq!(jumpis.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_source_map() {
+    /*
+    PUSH1 0x2a POP STOP
+    */
+    let code = hex!("602a5000");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let location = SourceLocation { file: "Counter.sol".to_string(), line: 7, column: 9 };
+    let source_map = SourceMap::from_iter([(2, location.clone())]);
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth())
+        .with_source_map(HashMap::from_iter([(addr, source_map)]));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let steps = &insp.traces().nodes()[0].trace.steps;
+    assert_eq!(steps.len(), 3);
+    assert_eq!(steps[1].pc, 2);
+    assert_eq!(steps[1].source_location.as_deref(), Some(&location));
+    assert!(steps[0].source_location.is_none());
+    assert!(steps[2].source_location.is_none());
+}
+
+#[test]
+fn test_out_of_gas_error() {
+    /*
+    JUMPDEST PUSH0 JUMP
+    */
+    let code = hex!("5b5f56");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(TxEnv { gas_limit: 30000, ..call_tx(addr) }).unwrap();
+    assert!(!res.result.is_success());
+
+    let error = insp.traces().nodes()[0].trace.error().unwrap();
+    assert_eq!(error, TraceError::OutOfGas);
+    assert_eq!(error.to_string(), "out of gas");
+    assert!(insp.traces().is_out_of_gas());
+}
+
+#[test]
+fn test_to_dot() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B reverts:
+
+    PUSH0 PUSH0 REVERT
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("5f5ffd")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    let dot = insp.traces().to_dot();
+    assert!(dot.starts_with("digraph calls {\n"));
+    assert!(dot.contains(&format!(
+        "    0 [label=\"{}→{a}\\ngas: {}\"];\n",
+        Address::ZERO,
+        nodes[0].trace.gas_used
+    )));
+    assert!(dot.contains(&format!(
+        "    1 [label=\"{a}→{b}\\ngas: {}\\nreverted\", color=red];\n",
+        nodes[1].trace.gas_used
+    )));
+    assert!(dot.contains("    0 -> 1 [label=\"1\"];\n"));
+    assert!(dot.ends_with('}'));
+}
+
+#[test]
+fn test_opcodes_filter() {
+    /*
+    PUSH1 0x02 PUSH1 0x03 ADD PUSH0 SSTORE PUSH1 0x04 PUSH1 0x05 MUL PUSH1 0x01 SSTORE STOP
+    */
+    let code = hex!("60026003015f55600460050260015500");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let config = TracingInspectorConfig::default_geth()
+        .with_opcodes_filter(OpcodeFilter::new().enabled(OpCode::SSTORE));
+    let mut insp = TracingInspector::new(config);
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let steps = &insp.traces().nodes()[0].trace.steps;
+    assert_eq!(steps.len(), 2);
+    assert!(steps.iter().all(|step| step.op == OpCode::SSTORE));
+    assert_eq!(steps[0].storage_change.as_ref().unwrap().value, U256::from(5));
+    assert_eq!(steps[1].storage_change.as_ref().unwrap().value, U256::from(20));
+}
+
+#[test]
+fn test_size_only_data_capture() {
+    /*
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+    */
+    let code = hex!("602a5f5260205ff3");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut traces = Vec::new();
+    for config in [
+        TracingInspectorConfig::default_parity(),
+        TracingInspectorConfig::default_parity().size_only_data(),
+    ] {
+        let mut insp = TracingInspector::new(config);
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(TxEnv { data: hex!("deadbeef").into(), ..call_tx(addr) }).unwrap();
+        assert!(res.result.is_success());
+        traces.push(insp.into_traces().into_nodes().remove(0).trace);
+    }
+
+    let [full, size_only] = &traces[..] else { unreachable!() };
+    assert_eq!(full.data.len(), 4);
+    assert_eq!(full.output.len(), 32);
+    assert_eq!(full.input_size, size_only.input_size);
+    assert_eq!(full.output_size, size_only.output_size);
+    assert_eq!(size_only.input_size, 4);
+    assert_eq!(size_only.output_size, 32);
+    assert!(size_only.data.is_empty());
+    assert!(size_only.output.is_empty());
+}
+
+#[test]
+fn test_size_only_deployed_code_size() {
+    /*
+    The init code deploys `PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN`:
+
+    PUSH1 0x08 PUSH1 0x0a PUSH0 CODECOPY PUSH1 0x08 PUSH0 RETURN
+    */
+    let init_code = hex!("6008600a5f3960085ff3602a5f5260205ff3");
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity().size_only_data());
+    let mut evm = contract_context(&[]).build_mainnet_with_inspector(&mut insp);
+    let res = evm
+        .inspect_tx(TxEnv {
+            kind: TransactTo::Create,
+            data: init_code.into(),
+            ..call_tx(Address::ZERO)
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    let node = &insp.traces().nodes()[0];
+    assert!(node.trace.output.is_empty());
+    assert_eq!(node.deployed_code_size(), Some(8));
+}
+
+#[test]
+fn test_created_contracts() {
+    /*
+    The factory calls the deployer and then creates a contract itself:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <deployer> GAS CALL POP
+    PUSH3 0x5f5ff3 PUSH0 MSTORE PUSH1 0x03 PUSH1 0x1d PUSH0 CREATE POP STOP
+
+    The deployer creates a contract:
+
+    PUSH3 0x5f5ff3 PUSH0 MSTORE PUSH1 0x03 PUSH1 0x1d PUSH0 CREATE POP STOP
+
+    The init code deploys an empty contract:
+
+    PUSH0 PUSH0 RETURN
+    */
+    let factory = address!("0x1000000000000000000000000000000000000001");
+    let deployer = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[(
+                    factory,
+                    &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af150625f5ff35f526003601d5ff05000"),
+                ),
+                (deployer, &hex!("625f5ff35f526003601d5ff05000"))]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(factory)).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes.len(), 4);
+    assert_eq!(nodes[1].trace.address, deployer);
+    assert_eq!(
+        insp.traces().created_contracts(),
+        vec![(1, deployer.create(0)), (0, factory.create(0))]
+    );
+}
+
+#[test]
+fn test_deployed_code() {
+    /*
+    The init code creates a child contract and deploys a single STOP:
+
+    PUSH10 <child init code> PUSH0 MSTORE PUSH1 0x0a PUSH1 0x16 PUSH0 CREATE POP
+    PUSH1 0x01 PUSH0 RETURN
+
+    The child init code deploys PUSH1 0x2a:
+
+    PUSH2 0x602a PUSH0 MSTORE PUSH1 0x02 PUSH1 0x1e RETURN
+    */
+    let init_code = hex!("6961602a5f526002601ef35f52600a60165ff05060015ff3");
+    let child_init_code = hex!("61602a5f526002601ef3");
+
+    let context = contract_context(&[]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm
+        .inspect_tx(TxEnv {
+            caller: Address::ZERO,
+            gas_limit: 1000000,
+            kind: TransactTo::Create,
+            data: init_code.into(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+    evm.ctx.db_mut().commit(res.state);
+
+    let contract = Address::ZERO.create(0);
+    let child = contract.create(1);
+    let deployed = evm.inspector.traces().deployed_code();
+    assert_eq!(deployed.len(), 2);
+    assert_eq!(deployed[&contract], (Bytes::from(init_code), Bytes::from(hex!("00"))));
+    assert_eq!(deployed[&child], (Bytes::from(child_init_code), Bytes::from(hex!("602a"))));
+
+    for (address, (_, runtime_code)) in deployed {
+        let code = evm.ctx.db_mut().load_account(address).unwrap().info.code.clone().unwrap();
+        assert_eq!(code.original_bytes(), runtime_code);
+    }
+}
+
+#[test]
+fn test_total_gas_used() {
+    let sender = address!("0x1000000000000000000000000000000000000001");
+    let receiver = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[]).modify_db_chained(|db| {
+        db.insert_account_info(
+            sender,
+            AccountInfo { balance: U256::from(1_000_000_000u64), ..Default::default() },
+        );
+    });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm
+        .inspect_tx(TxEnv {
+            caller: sender,
+            gas_limit: 1000000,
+            kind: TransactTo::Call(receiver),
+            value: U256::from(1),
+            data: hex!("deadbeef").into(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    // 21000 base cost + 4 non-zero calldata bytes
+    assert_eq!(res.result.gas_used(), 21064);
+    assert_eq!(insp.total_gas_used(), res.result.gas_used());
+}
+
+#[test]
+fn test_code_size_limit_error() {
+    /*
+    Returns 24577 zero bytes as the runtime code, one more than the limit of EIP-170:
+
+    PUSH2 0x6001 PUSH0 RETURN
+    */
+    let init_code = hex!("6160015ff3");
+
+    let context = contract_context(&[]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm
+        .inspect_tx(TxEnv {
+            caller: Address::ZERO,
+            gas_limit: 1000000,
+            kind: TransactTo::Create,
+            data: init_code.into(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(!res.result.is_success());
+
+    let error = insp.traces().nodes()[0].trace.error().unwrap();
+    assert_eq!(error, TraceError::CreateContractSizeLimit);
+    assert_eq!(error.to_string(), "max code size exceeded");
+}
+
+#[test]
+fn test_diff() {
+    /*
+    The caller calls the callee:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+
+    The callee returns a single word, 0x2a or 0x2b:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let trace = |callee_code: &[u8]| {
+        let context = contract_context(&[
+            (caller, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+            (callee, callee_code),
+        ]);
+
+        let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+        let mut evm = context.build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(caller)).unwrap();
+        assert!(res.result.is_success());
+        insp.into_traces()
+    };
+
+    let old = trace(&hex!("602a5f5260205ff3"));
+    let new = trace(&hex!("602b5f5260205ff3"));
+
+    assert!(old.diff(&old).is_empty());
+    assert_eq!(
+        old.diff_with(&new, true),
+        vec![TraceDiff::Output {
+            frame: 1,
+            left: U256::from(0x2a).to_be_bytes_vec().into(),
+            right: U256::from(0x2b).to_be_bytes_vec().into(),
+        }]
+    );
+}
+
+#[test]
+fn test_push_value() {
+    /*
+    PUSH2 0x1234 PUSH0 ADD POP STOP
+    */
+    let code = hex!("6112345f015000");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let config =
+        TracingInspectorConfig::default_geth().disable_stack_snapshots().record_immediate_bytes();
+    let mut insp = TracingInspector::new(config);
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let steps = &insp.traces().nodes()[0].trace.steps;
+    let values = steps.iter().map(|step| step.push_value()).collect::<Vec<_>>();
+    assert_eq!(values, [Some(U256::from(0x1234)), Some(U256::ZERO), None, None, None]);
+}
+
+#[test]
+fn test_out_of_gas_frames() {
+    /*
+    A calls B with 100 gas:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> PUSH1 0x64 CALL POP STOP
+
+    B loops forever:
+
+    JUMPDEST PUSH0 JUMP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000026064f15000")),
+        (b, &hex!("5b5f56")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    assert_eq!(insp.traces().out_of_gas_frames(), vec![1]);
+    assert!(!insp.traces().is_out_of_gas());
+}
+
+#[test]
+fn test_create_init_code() {
+    /*
+    Stores its constructor argument, a single word appended to the creation code:
+
+    PUSH1 0x20 PUSH1 0x0b PUSH0 CODECOPY PUSH0 MLOAD PUSH0 SSTORE STOP
+    */
+    let creation_code = hex!("6020600b5f395f515f5500");
+    let init_code: Bytes =
+        [&creation_code[..], U256::from(0x2a).to_be_bytes::<32>().as_slice()].concat().into();
+
+    let context = contract_context(&[]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm
+        .inspect_tx(TxEnv {
+            caller: Address::ZERO,
+            gas_limit: 1000000,
+            kind: TransactTo::Create,
+            data: init_code.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    let trace = &insp.traces().nodes()[0].trace;
+    assert_eq!(trace.init_code, Some(init_code));
+    // the constructor read its argument from the end of the init code
+    let storage = &res.state[&trace.address].storage;
+    assert_eq!(storage[&U256::ZERO].present_value, U256::from(0x2a));
+}
+
+#[test]
+fn test_forwarded_revert_reason() {
+    /*
+    The outer contract calls the inner contract and reverts without data if the call failed:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <inner> GAS CALL PUSH1 0x22 JUMPI PUSH0 PUSH0 REVERT
+    JUMPDEST STOP
+
+    The inner contract reverts with `Error("inner fail")`, appended to its code:
+
+    PUSH1 0x64 PUSH1 0x0a PUSH0 CODECOPY PUSH1 0x64 PUSH0 REVERT
+    */
+    let outer = address!("0x1000000000000000000000000000000000000001");
+    let inner = address!("0x2000000000000000000000000000000000000002");
+    let outer_code =
+        hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af16022575f5ffd5b00");
+    let inner_code =
+        [&hex!("6064600a5f3960645ffd")[..], &Revert::from("inner fail").abi_encode()].concat();
+
+    let context = contract_context(&[(outer, &outer_code), (inner, &inner_code)]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(outer)).unwrap();
+    assert!(!res.result.is_success());
+
+    let traces = insp.traces();
+    let nodes = traces.nodes();
+    assert!(nodes[0].trace.output.is_empty());
+    assert_eq!(nodes[0].trace.forwarded_revert_reason.as_deref(), Some("inner fail"));
+    assert_eq!(traces.revert_origin(0).map(|node| node.idx), Some(1));
+
+    // the inner frame has its own revert data
+    assert_eq!(nodes[1].trace.output, Revert::from("inner fail").abi_encode());
+    assert_eq!(nodes[1].trace.forwarded_revert_reason, None);
+    assert_eq!(nodes[1].trace.custom_error, None);
+
+    // the reason can't be decoded if the output of the inner frame isn't recorded
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity().size_only_data());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(outer)).unwrap();
+    assert!(!res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(insp.traces().revert_origin(0).map(|node| node.idx), Some(1));
+    assert_eq!(nodes[0].trace.forwarded_revert_reason, None);
+}
+
+#[test]
+fn test_custom_error() {
+    sol! {
+        error Unauthorized(address caller);
+    }
+
+    /*
+    The contract reverts with `Unauthorized(caller)`, appended to its code:
+
+    PUSH1 0x24 PUSH1 0x0a PUSH0 CODECOPY PUSH1 0x24 PUSH0 REVERT
+    */
+    let contract = address!("0x1000000000000000000000000000000000000001");
+    let caller = address!("0x3000000000000000000000000000000000000003");
+    let error = Unauthorized { caller }.abi_encode();
+    let code = [&hex!("6024600a5f3960245ffd")[..], &error].concat();
+
+    let context = contract_context(&[(contract, &code)]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(contract)).unwrap();
+    assert!(!res.result.is_success());
+
+    let trace = &insp.traces().nodes()[0].trace;
+    assert_eq!(trace.output, error);
+    let (selector, data) = trace.custom_error.clone().unwrap();
+    assert_eq!(selector, Unauthorized::SELECTOR);
+    assert_eq!(data, error[4..]);
+    assert_eq!(Address::from_word(B256::from_slice(&data)), caller);
+}
+
+#[test]
+fn test_custom_error_without_arguments() {
+    sol! {
+        error Paused();
+    }
+
+    /*
+    The contract reverts with the bare selector of `Paused()`, appended to its code:
+
+    PUSH1 0x04 PUSH1 0x0a PUSH0 CODECOPY PUSH1 0x04 PUSH0 REVERT
+    */
+    let contract = address!("0x1000000000000000000000000000000000000001");
+    let code = [&hex!("6004600a5f3960045ffd")[..], &Paused::SELECTOR[..]].concat();
+
+    let context = contract_context(&[(contract, &code)]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(contract)).unwrap();
+    assert!(!res.result.is_success());
+
+    let trace = &insp.traces().nodes()[0].trace;
+    assert_eq!(trace.output, Paused::SELECTOR[..]);
+    assert_eq!(trace.custom_error, Some((Paused::SELECTOR.into(), Bytes::new())));
+}
+
+#[test]
+fn test_data_hashes() {
+    /*
+    The caller calls the callee twice with the word 0x11 and once with the word 0x22:
+
+    PUSH1 0x11 PUSH0 MSTORE
+    PUSH0 PUSH0 PUSH1 0x20 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP
+    PUSH0 PUSH0 PUSH1 0x20 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP
+    PUSH1 0x22 PUSH0 MSTORE
+    PUSH0 PUSH0 PUSH1 0x20 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP
+    STOP
+
+    The callee does nothing:
+
+    STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[(
+                    caller,
+                    &hex!("60115f525f5f60205f5f7320000000000000000000000000000000000000025af1505f5f60205f5f7320000000000000000000000000000000000000025af15060225f525f5f60205f5f7320000000000000000000000000000000000000025af15000"),
+                ),
+                (callee, &hex!("00"))]);
+
+    let mut insp = TracingInspector::new(
+        TracingInspectorConfig::default_parity().size_only_data().data_hashes(),
+    );
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes.len(), 4);
+    let input_hashes =
+        nodes[1..].iter().map(|node| node.trace.input_hash.unwrap()).collect::<Vec<_>>();
+    assert_eq!(input_hashes[0], keccak256(U256::from(0x11).to_be_bytes::<32>()));
+    assert_eq!(input_hashes[0], input_hashes[1]);
+    assert_ne!(input_hashes[0], input_hashes[2]);
+    assert!(nodes[1..]
+        .iter()
+        .all(|node| node.trace.data.is_empty() && node.trace.output_hash == Some(KECCAK_EMPTY)));
+}
+
+#[test]
+fn test_static_call_violation() {
+    /*
+    The caller staticcalls the callee:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS STATICCALL POP STOP
+
+    The callee attempts to write a slot:
+
+    PUSH1 0x01 PUSH0 SSTORE STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (caller, &hex!("5f5f5f5f7320000000000000000000000000000000000000025afa5000")),
+        (callee, &hex!("60015f5500")),
+    ]);
+
+    // the opcode is recorded even if the steps are not
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes[0].trace.error(), None);
+    let error = nodes[1].trace.error().unwrap();
+    assert_eq!(error, TraceError::StaticCallViolation(Some(OpCode::SSTORE)));
+    assert_eq!(error.to_string(), "write protection");
+}
+
+#[test]
+fn test_touched_addresses() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B reads the balance of C:
+
+    PUSH20 <c> BALANCE POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let c = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("733000000000000000000000000000000000000003315000")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    assert_eq!(insp.traces().touched_addresses(), HashSet::from_iter([Address::ZERO, a, b, c]));
+}
+
+#[test]
+fn test_gas_cost_overrides() {
+    /*
+    PUSH1 0x2a PUSH0 SSTORE STOP
+    */
+    let code = hex!("602a5f5500");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth())
+        .with_gas_cost_overrides(HashMap::from_iter([(opcode::SSTORE, 100)]));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let gas_costs = insp.traces().nodes()[0]
+        .trace
+        .steps
+        .iter()
+        .map(|step| (step.op, step.gas_cost))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        gas_costs,
+        [(OpCode::PUSH1, 3), (OpCode::PUSH0, 2), (OpCode::SSTORE, 100), (OpCode::STOP, 0)]
+    );
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_frame_metadata() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B does nothing:
+
+    STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("00")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    insp.on_frame(move |node| (node.trace.address == b).then(|| serde_json::json!({"tag": "hot"})));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let traces = insp.traces();
+    assert_eq!(traces.nodes()[0].metadata, None);
+    assert_eq!(traces.nodes()[1].metadata, Some(serde_json::json!({"tag": "hot"})));
+
+    let json = serde_json::to_string(traces).unwrap();
+    assert_eq!(&serde_json::from_str::<CallTraceArena>(&json).unwrap(), traces);
+}
+
+#[test]
+fn test_precompile_calls() {
+    /*
+    Calls the identity precompile with a single word:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH1 0x20 PUSH1 0x20 PUSH0 PUSH0 PUSH1 0x04 GAS CALL POP
+    STOP
+    */
+    let code = hex!("602a5f526020602060205f5f60045af15000");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+    let identity = address!("0x0000000000000000000000000000000000000004");
+
+    let context = contract_context(&[(addr, &code)]);
+
+    let mut insp = TracingInspector::new(
+        TracingInspectorConfig::default_parity().set_exclude_precompile_calls(false),
+    );
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(addr)).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes[0].children, [1]);
+    assert!(!nodes[0].trace.is_precompile);
+
+    let precompile = &nodes[1].trace;
+    assert!(precompile.is_precompile);
+    assert_eq!(precompile.address, identity);
+    assert_eq!(precompile.output[..], U256::from(0x2a).to_be_bytes::<32>());
+    // 15 base cost + 3 per word
+    assert_eq!(precompile.gas_used, 18);
+}
+
+#[test]
+fn test_frame_for_log() {
+    /*
+    A emits a log, calls B and C and emits another log:
+
+    PUSH0 PUSH0 LOG0
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <c> GAS CALL POP
+    PUSH0 PUSH0 LOG0 STOP
+
+    B emits a log:
+
+    PUSH0 PUSH0 LOG0 STOP
+
+    C emits a log and reverts:
+
+    PUSH0 PUSH0 LOG0 PUSH0 PUSH0 REVERT
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let c = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[(
+                    a,
+                    &hex!("5f5fa05f5f5f5f5f7320000000000000000000000000000000000000025af1505f5f5f5f5f7330000000000000000000000000000000000000035af1505f5fa000"),
+                ),
+                (b, &hex!("5f5fa000")),
+                (c, &hex!("5f5fa05f5ffd"))]);
+
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::default_parity().set_record_logs(true));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert_eq!(res.result.logs().len(), 3);
+
+    // the log of C is reverted
+    let traces = insp.traces();
+    let frames = (0..4).map(|idx| traces.frame_for_log(idx)).collect::<Vec<_>>();
+    assert_eq!(frames, [Some(0), Some(1), Some(0), None]);
+}
+
+#[test]
+fn test_call_args_region() {
+    /*
+    An EIP-1167 minimal proxy that forwards its calldata to the implementation:
+
+    CALLDATASIZE RETURNDATASIZE RETURNDATASIZE CALLDATACOPY RETURNDATASIZE RETURNDATASIZE
+    RETURNDATASIZE CALLDATASIZE RETURNDATASIZE PUSH20 <implementation> GAS DELEGATECALL
+    RETURNDATASIZE DUP3 DUP1 RETURNDATACOPY SWAP1 RETURNDATASIZE SWAP2 PUSH1 0x2b JUMPI REVERT
+    JUMPDEST RETURN
+
+    The implementation does nothing:
+
+    STOP
+    */
+    let proxy = address!("0x1000000000000000000000000000000000000001");
+    let implementation = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[(
+                    proxy,
+                    &hex!("363d3d373d3d3d363d7320000000000000000000000000000000000000025af43d82803e903d91602b57fd5bf3"),
+                ),
+                (implementation, &hex!("00"))]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let calldata = [&hex!("a9059cbb")[..], &[0x11; 32]].concat();
+    let res = evm.inspect_tx(TxEnv { data: calldata.clone().into(), ..call_tx(proxy) }).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes[0].trace.args_offset, None);
+    assert_eq!(nodes[0].trace.args_size, None);
+
+    let delegate = &nodes[1].trace;
+    assert!(delegate.kind.is_delegate());
+    assert_eq!(delegate.data, calldata);
+    assert_eq!(delegate.args_offset, Some(0));
+    assert_eq!(delegate.args_size, Some(36));
+}
+
+#[test]
+fn test_deterministic_traces() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B calls C:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <c> GAS CALL POP STOP
+
+    C writes a slot:
+
+    PUSH1 0x2a PUSH0 SSTORE STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let c = address!("0x3000000000000000000000000000000000000003");
+
+    let mut db = CacheDB::<EmptyDB>::default();
+    for (address, code) in [
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")[..]),
+        (b, &hex!("5f5f5f5f5f7330000000000000000000000000000000000000035af15000")[..]),
+        (c, &hex!("602a5f5500")[..]),
+    ] {
+        db.insert_account_info(
+            address,
+            AccountInfo {
+                code: Some(Bytecode::new_raw(code.to_vec().into())),
+                ..Default::default()
+            },
+        );
+    }
+
+    let tx = call_tx(a);
+    assert!(verify_deterministic(db, tx, || TracingInspector::new(TracingInspectorConfig::all())));
+}
+
+/// Forwards to a [TracingInspector] and interrupts it once the first call made by the root call
+/// completed.
+struct InterruptAfterFirstCall(TracingInspector);
+
+impl<CTX> Inspector<CTX> for InterruptAfterFirstCall
+where
+    CTX: ContextTr<Journal: JournalExt>,
+{
+    fn step(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+        self.0.step(interp, context);
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+        self.0.step_end(interp, context);
+    }
+
+    fn log(&mut self, interp: &mut Interpreter, context: &mut CTX, log: Log) {
+        self.0.log(interp, context, log);
+    }
+
+    fn call(&mut self, context: &mut CTX, inputs: &mut CallInputs) -> Option<CallOutcome> {
+        self.0.call(context, inputs)
+    }
+
+    fn call_end(&mut self, context: &mut CTX, inputs: &CallInputs, outcome: &mut CallOutcome) {
+        self.0.call_end(context, inputs, outcome);
+        if self.0.traces().nodes().len() > 1 {
+            self.0.set_interrupt();
+        }
+    }
+}
+
+#[test]
+fn test_interrupt() {
+    /*
+    Caller calls B and C and emits a log:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <c> GAS CALL POP
+    PUSH1 0x01 PUSH0 PUSH0 LOG1 STOP
+
+    B and C stop immediately:
+
+    STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let c = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[(
+                    caller,
+                    &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1505f5f5f5f5f7330000000000000000000000000000000000000035af15060015f5fa100"),
+                ),
+                (b, &hex!("00")),
+                (c, &hex!("00"))]);
+
+    let mut insp = InterruptAfterFirstCall(TracingInspector::new(TracingInspectorConfig::all()));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let insp = &mut insp.0;
+    assert!(insp.is_interrupted());
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[0].children, vec![1]);
+    assert_eq!(nodes[1].trace.address, b);
+    assert!(nodes[0].logs.is_empty());
+    // the root call is still completed
+    assert!(nodes[0].trace.success);
+    assert!(nodes[0].trace.steps.last().unwrap().op == OpCode::CALL);
+
+    insp.fuse();
+    assert!(!insp.is_interrupted());
+}
+
+#[test]
+fn test_selfdestruct_created_in_same_tx() {
+    /*
+    The init code selfdestructs to the beneficiary:
+
+    PUSH20 <beneficiary> SELFDESTRUCT
+    */
+    let beneficiary = address!("0x3000000000000000000000000000000000000003");
+    let value = U256::from(100);
+
+    let context = contract_context(&[]).modify_db_chained(|db| {
+        db.insert_account_info(Address::ZERO, AccountInfo { balance: value, ..Default::default() });
+    });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm
+        .inspect_tx(TxEnv {
+            caller: Address::ZERO,
+            gas_limit: 1000000,
+            kind: TransactTo::Create,
+            value,
+            data: hex!("733000000000000000000000000000000000000003ff").into(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    let trace = &insp.traces().nodes()[0].trace;
+    let selfdestruct = trace.selfdestruct().unwrap();
+    assert_eq!(selfdestruct.contract, trace.address);
+    assert_eq!(selfdestruct.beneficiary, beneficiary);
+    assert_eq!(selfdestruct.balance, value);
+    assert!(selfdestruct.actually_destroyed);
+    assert_eq!(res.state[&beneficiary].info.balance, value);
+}
+
+#[test]
+fn test_selfdestruct_existing_contract() {
+    /*
+    The contract selfdestructs to the beneficiary:
+
+    PUSH20 <beneficiary> SELFDESTRUCT
+    */
+    let contract = address!("0x1000000000000000000000000000000000000001");
+    let beneficiary = address!("0x3000000000000000000000000000000000000003");
+    let balance = U256::from(100);
+    let code = hex!("733000000000000000000000000000000000000003ff");
+
+    let context = contract_context(&[]).modify_db_chained(|db| {
+        db.insert_account_info(
+            contract,
+            AccountInfo {
+                balance,
+                code: Some(Bytecode::new_raw(code.to_vec().into())),
+                ..Default::default()
+            },
+        );
+    });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(contract)).unwrap();
+    assert!(res.result.is_success());
+
+    let selfdestruct = insp.traces().nodes()[0].trace.selfdestruct().unwrap();
+    assert_eq!(selfdestruct.contract, contract);
+    assert_eq!(selfdestruct.beneficiary, beneficiary);
+    assert_eq!(selfdestruct.balance, balance);
+    // only the balance is transferred since Cancun
+    assert!(!selfdestruct.actually_destroyed);
+    assert_eq!(res.state[&beneficiary].info.balance, balance);
+    assert!(!res.state[&contract].is_selfdestructed());
+}
+
+#[test]
+fn test_top_level_output() {
+    sol! {
+        function number() external view returns (uint256);
+    }
+
+    /*
+    The contract returns 42 for any call:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN
+    */
+    let contract = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(contract, &hex!("602a5f5260205ff3"))]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    assert_eq!(insp.traces().top_level_output(), None);
+
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm
+        .inspect_tx(TxEnv { data: numberCall {}.abi_encode().into(), ..call_tx(contract) })
+        .unwrap();
+    assert!(res.result.is_success());
+
+    let output = insp.traces().top_level_output().unwrap();
+    assert_eq!(numberCall::abi_decode_returns(output).unwrap(), U256::from(42));
+}
+
+#[test]
+fn test_forwarded_gas_limit() {
+    /*
+    Caller calls the callee with more gas than available, then with 10000 gas:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> PUSH4 0xffffffff CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> PUSH2 0x2710 CALL POP STOP
+
+    Callee stops immediately:
+
+    STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[(
+                    caller,
+                    &hex!("5f5f5f5f5f73200000000000000000000000000000000000000263fffffffff1505f5f5f5f5f732000000000000000000000000000000000000002612710f15000"),
+                ),
+                (callee, &hex!("00"))]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes[0].trace.gas_limit, 1000000 - 21000);
+
+    // the first call is capped to 63/64 of the gas left after the cold account access
+    let call = nodes[0].trace.steps.iter().find(|step| step.op == OpCode::CALL).unwrap();
+    let available = call.gas_remaining - 2600;
+    assert_eq!(nodes[1].trace.gas_limit, available - available / 64);
+    assert_eq!(nodes[2].trace.gas_limit, 10000);
+}
+
+#[test]
+fn test_gas_forwarding() {
+    /*
+    Caller calls the callee with all of its gas, then with 10000 gas and a value of 1 wei:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH1 0x01 PUSH20 <callee> PUSH2 0x2710 CALL POP STOP
+
+    Callee stops immediately:
+
+    STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (
+            caller,
+            &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1505f5f5f5f6001732000000000000000000000000000000000000002612710f15000"),
+        ),
+        (callee, &hex!("00")),
+    ])
+    .modify_db_chained(|db| {
+        db.cache.accounts.get_mut(&caller).unwrap().info.balance = U256::from(1);
+    });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let forwards = insp.traces().gas_forwarding();
+    assert_eq!(forwards.len(), 2);
+    for forward in &forwards {
+        assert_eq!(forward.gas_given, forward.expected_gas());
+    }
+
+    // the gas left after the intrinsic gas, the pushes and the cold account access
+    let caller_gas = 1000000 - 21000 - 15 - 2600;
+    assert_eq!(forwards[0].idx, 1);
+    assert_eq!(forwards[0].caller_gas, caller_gas);
+    assert_eq!(forwards[0].requested_gas, 1000000 - 21000 - 15);
+    assert_eq!(forwards[0].stipend, 0);
+    assert_eq!(forwards[0].gas_given, caller_gas - caller_gas / 64);
+
+    // the explicit amount is less than 63/64 of the available gas, the stipend comes on top
+    assert_eq!(forwards[1].idx, 2);
+    assert_eq!(forwards[1].requested_gas, 10000);
+    assert_eq!(forwards[1].stipend, 2300);
+    assert_eq!(forwards[1].gas_given, 12300);
+}
+
+#[test]
+#[cfg(feature = "msgpack")]
+fn test_msgpack_roundtrip() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B emits a log and returns a word:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x01 PUSH1 0x20 PUSH0 LOG1 PUSH1 0x20 PUSH0 RETURN
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("602a5f52600160205fa160205ff3")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::all());
+    insp.on_frame(|node| Some(serde_json::json!({"depth": node.trace.depth})));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let traces = insp.traces();
+    assert_eq!(traces.nodes().len(), 2);
+    assert_eq!(traces.nodes()[1].logs.len(), 1);
+
+    let bytes = traces.to_msgpack();
+    assert!(bytes.len() < serde_json::to_vec(traces).unwrap().len());
+    assert_eq!(&CallTraceArena::from_msgpack(&bytes).unwrap(), traces);
+}
+
+#[test]
+fn test_call_only_tracing() {
+    /*
+    A calls B with a word of calldata:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH0 PUSH0 PUSH1 0x20 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B does nothing:
+
+    STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("602a5f525f5f60205f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("00")),
+    ]);
+
+    let steps = Arc::new(AtomicUsize::new(0));
+    let mut insp = TracingInspector::new(TracingInspectorConfig::call_tracer());
+    insp.on_step({
+        let steps = steps.clone();
+        move |_, _| {
+            steps.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    assert_eq!(steps.load(Ordering::Relaxed), 0);
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes.len(), 2);
+    assert!(nodes.iter().all(|node| node.trace.steps.is_empty()));
+    // the call opcode is still inspected for the fields of the call
+    assert_eq!(nodes[1].trace.args_offset, Some(0));
+    assert_eq!(nodes[1].trace.args_size, Some(32));
+}
+
+#[test]
+fn test_skipped_call_steps() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B runs a few opcodes:
+
+    PUSH0 POP PUSH0 POP STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("5f505f5000")),
+    ]);
+
+    let steps = Arc::new(AtomicUsize::new(0));
+    let mut insp = TracingInspector::new(
+        TracingInspectorConfig::default_parity().steps().set_only_top_call(true),
+    );
+    insp.on_step({
+        let steps = steps.clone();
+        move |_, _| {
+            steps.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    // the steps of the skipped call are not recorded in the top call
+    let nodes = insp.traces().nodes();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].trace.steps.len(), 10);
+    assert_eq!(steps.load(Ordering::Relaxed), 10);
+}
+
+#[test]
+fn test_transaction_fees() {
+    let caller = address!("0x3000000000000000000000000000000000000003");
+    let contract = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(contract, &hex!("00"))])
+        .modify_db_chained(|db| {
+            db.insert_account_info(
+                caller,
+                AccountInfo { balance: U256::from(u64::MAX), ..Default::default() },
+            );
+        })
+        .modify_block_chained(|block| block.basefee = 50);
+
+    let fees = |tx_type: u8, gas_price: u128, gas_priority_fee: Option<u128>| {
+        let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm
+            .inspect_tx(TxEnv {
+                tx_type,
+                caller,
+                gas_limit: 1000000,
+                gas_price,
+                gas_priority_fee,
+                kind: TransactTo::Call(contract),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(res.result.is_success());
+        insp.transaction_fees().unwrap()
+    };
+
+    // EIP-1559: the base fee plus the priority fee, below the max fee
+    let eip1559 = fees(2, 100, Some(10));
+    assert_eq!(eip1559.effective_gas_price, 60);
+    assert_eq!(eip1559.base_fee, 50);
+    assert_eq!(eip1559.priority_fee, 10);
+    assert_eq!(eip1559.blob_gas_price, None);
+
+    // EIP-1559: capped by the max fee
+    let capped = fees(2, 55, Some(10));
+    assert_eq!(capped.effective_gas_price, 55);
+    assert_eq!(capped.priority_fee, 5);
+
+    // legacy: the gas price
+    let legacy = fees(0, 70, None);
+    assert_eq!(legacy.effective_gas_price, 70);
+    assert_eq!(legacy.priority_fee, 20);
+}
+
+#[test]
+fn test_calls_to_empty_accounts() {
+    /*
+    Caller calls the contract, the externally owned account and the identity precompile, then
+    tries to send a wei it doesn't have to the externally owned account:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <contract> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <eoa> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH1 0x04 GAS STATICCALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH1 0x01 PUSH20 <eoa> GAS CALL POP STOP
+
+    The contract stops immediately:
+
+    STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let contract = address!("0x2000000000000000000000000000000000000002");
+    let eoa = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[
+        (
+            caller,
+            &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1505f5f5f5f5f7330000000000000000000000000000000000000035af1505f5f5f5f60045afa505f5f5f5f60017330000000000000000000000000000000000000035af15000"),
+        ),
+        (contract, &hex!("00")),
+    ])
+    .modify_db_chained(|db| {
+        db.insert_account_info(eoa, AccountInfo { balance: U256::from(1), ..Default::default() });
+    });
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let traces = insp.traces();
+    assert_eq!(traces.nodes().len(), 5);
+    assert!(traces.nodes()[2].trace.success);
+    assert!(traces.nodes()[3].trace.is_precompile);
+    // the transfer fails before the call starts
+    assert_eq!(traces.nodes()[4].trace.status, Some(InstructionResult::OutOfFunds));
+    assert_eq!(traces.calls_to_empty_accounts(), vec![(2, eoa)]);
+}
+
+#[test]
+#[cfg(feature = "optimism")]
+fn test_deposit_transaction() {
+    use op_revm::{transaction::deposit::DepositTransactionParts, OpTransaction};
+
+    let contract = address!("0x1000000000000000000000000000000000000001");
+    let context = contract_context(&[(contract, &hex!("00"))]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(contract)).unwrap();
+    assert!(res.result.is_success());
+
+    // a regular transaction is not a deposit
+    insp.set_deposit_transaction(&OpTransaction::new(call_tx(contract)));
+    assert_eq!(insp.traces().nodes()[0].trace.mint, None);
+    assert_eq!(insp.traces().nodes()[0].trace.source_hash, None);
+
+    let source_hash = B256::with_last_byte(1);
+    insp.set_deposit_transaction(&OpTransaction {
+        deposit: DepositTransactionParts::new(source_hash, Some(100), false),
+        ..OpTransaction::new(call_tx(contract))
+    });
+    assert_eq!(insp.traces().nodes()[0].trace.mint, Some(U256::from(100)));
+    assert_eq!(insp.traces().nodes()[0].trace.source_hash, Some(source_hash));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_append_to_deserialized_arena() {
+    /*
+    A calls B:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B emits an empty log:
+
+    PUSH0 PUSH0 LOG0 STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("5f5fa000")),
+    ]);
+
+    let trace = |to: Address| {
+        let mut insp =
+            TracingInspector::new(TracingInspectorConfig::default_parity().record_logs());
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(to)).unwrap();
+        assert!(res.result.is_success());
+        insp.into_traces()
+    };
+
+    // checkpoint the arena of the first transaction and continue with the second one
+    let json = serde_json::to_string(&trace(b)).unwrap();
+    let mut arena = serde_json::from_str::<CallTraceArena>(&json).unwrap();
+    assert_eq!(arena.nodes().len(), 1);
+    arena.append(trace(a));
+
+    let nodes = arena.nodes();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(arena.roots().map(|node| node.trace.address).collect::<Vec<_>>(), vec![b, a]);
+    assert_eq!(nodes[1].children, vec![2]);
+    assert_eq!(nodes[2].parent, Some(1));
+    assert_eq!(nodes[2].trace.address, b);
+    // the log of the second transaction follows the log of the first one
+    assert_eq!(nodes[0].logs[0].index, 0);
+    assert_eq!(nodes[2].logs[0].index, 1);
+    assert_eq!(arena.frame_for_log(1), Some(2));
+}
+
+#[test]
+fn test_max_frames() {
+    /*
+    Caller calls the callee 100 times:
+
+    PUSH1 0x64
+    JUMPDEST PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP
+    PUSH1 0x01 SWAP1 SUB DUP1 PUSH1 0x02 JUMPI STOP
+
+    The callee stops immediately:
+
+    STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[(
+                    caller,
+                    &hex!("60645b5f5f5f5f5f7320000000000000000000000000000000000000025af150600190038060025700"),
+                ),
+                (callee, &hex!("00"))]);
+
+    let trace = |max_frames| {
+        let config = TracingInspectorConfig::default_parity().set_max_frames(max_frames);
+        let mut insp = TracingInspector::new(config);
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(caller)).unwrap();
+        assert!(res.result.is_success());
+        (insp.traces().nodes().len(), insp.frames_truncated())
+    };
+
+    assert_eq!(trace(None), (101, false));
+    assert_eq!(trace(Some(101)), (101, false));
+    assert_eq!(trace(Some(10)), (10, true));
+    assert_eq!(trace(Some(0)), (1, true));
+}
+
+#[test]
+#[cfg(feature = "profiling")]
+fn test_profiling_stats() {
+    /*
+    The caller adds two numbers and calls the callee:
+
+    PUSH1 0x01 PUSH1 0x02 ADD POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+
+    The callee stops immediately:
+
+    STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (caller, &hex!("6001600201505f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (callee, &hex!("00")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_geth());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let profile = insp.profiling_stats();
+    let steps = insp.traces().nodes().iter().map(|node| node.trace.steps.len()).sum::<usize>();
+    assert_eq!(steps, 15);
+    assert_eq!(profile.step_count, steps as u64);
+    assert!(!profile.total_step_time.is_zero());
+    assert!(!profile.total_call_time.is_zero());
+
+    insp.fuse();
+    assert_eq!(insp.profiling_stats().step_count, 0);
+}
+
+#[test]
+fn test_breakpoints() {
+    /*
+    Caller stores a word, adds two numbers and delegatecalls the library:
+
+    PUSH1 0x2a PUSH0 MSTORE PUSH1 0x01 PUSH1 0x02 ADD POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <library> GAS DELEGATECALL POP STOP
+
+    Library pushes a number:
+
+    PUSH1 0x03 STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let library = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (
+            caller,
+            &hex!("602a5f526001600201505f5f5f5f7320000000000000000000000000000000000000025af45000"),
+        ),
+        (library, &hex!("600300")),
+    ]);
+
+    // steps are not recorded, the breakpoints are captured regardless
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity())
+        .with_breakpoints(HashSet::from_iter([(caller, 8), (library, 2), (caller, 100)]));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let hits = insp.breakpoint_hits();
+    assert_eq!(hits.len(), 2);
+
+    // the ADD of the caller
+    assert_eq!(hits[0].code_address, caller);
+    assert_eq!(hits[0].pc, 8);
+    assert_eq!(hits[0].address, caller);
+    assert_eq!(hits[0].frame, 0);
+    assert_eq!(hits[0].stack, vec![U256::from(1), U256::from(2)]);
+    assert_eq!(hits[0].memory.as_bytes().as_ref(), U256::from(0x2a).to_be_bytes::<32>());
+
+    // the STOP of the library, executed in the context of the caller
+    assert_eq!(hits[1].code_address, library);
+    assert_eq!(hits[1].pc, 2);
+    assert_eq!(hits[1].address, caller);
+    assert_eq!(hits[1].frame, 1);
+    assert_eq!(hits[1].stack, vec![U256::from(3)]);
+    assert!(hits[1].memory.is_empty());
+    assert!(insp.traces().nodes().iter().all(|node| node.trace.steps.is_empty()));
+
+    insp.fuse();
+    assert!(insp.breakpoint_hits().is_empty());
+}
+
+#[test]
+fn test_breakpoints_in_skipped_call() {
+    /*
+    Caller calls the callee:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+
+    Callee pushes a number:
+
+    PUSH1 0x03 STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (caller, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (callee, &hex!("600300")),
+    ]);
+
+    // the call to the callee is not recorded, so neither is its breakpoint
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::default_parity().set_only_top_call(true))
+            .with_breakpoints(HashSet::from_iter([(caller, 0), (callee, 2)]));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    assert_eq!(insp.traces().nodes().len(), 1);
+    let hits = insp.breakpoint_hits();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].code_address, caller);
+    assert_eq!(hits[0].pc, 0);
+}
+
+/// Records the address of each create frame when it starts and the actual created address.
+struct RecordCreatedAddresses(TracingInspector, Vec<(Address, Option<Address>)>);
+
+impl<CTX> Inspector<CTX> for RecordCreatedAddresses
+where
+    CTX: ContextTr<Journal: JournalExt>,
+{
+    fn call(&mut self, context: &mut CTX, inputs: &mut CallInputs) -> Option<CallOutcome> {
+        self.0.call(context, inputs)
+    }
+
+    fn call_end(&mut self, context: &mut CTX, inputs: &CallInputs, outcome: &mut CallOutcome) {
+        self.0.call_end(context, inputs, outcome);
+    }
+
+    fn create(&mut self, context: &mut CTX, inputs: &mut CreateInputs) -> Option<CreateOutcome> {
+        let outcome = self.0.create(context, inputs);
+        let predicted = self.0.traces().nodes().last().unwrap().trace.address;
+        self.1.push((predicted, None));
+        outcome
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut CTX,
+        inputs: &CreateInputs,
+        outcome: &mut CreateOutcome,
+    ) {
+        self.0.create_end(context, inputs, outcome);
+        self.1.last_mut().unwrap().1 = outcome.address;
+    }
+}
+
+#[test]
+fn test_predict_create2_address() {
+    /*
+    Factory deploying a contract via CREATE2 with salt 0x45:
+
+    PUSH18 <init code> PUSH0 MSTORE
+    PUSH1 0x45 PUSH1 18 PUSH1 14 PUSH0 CREATE2
+    PUSH0 MSTORE PUSH1 32 PUSH0 RETURN
+
+    The init code deploys `PUSH1 0x2a PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN`.
+    */
+    let code = hex!("716008600a5f3960085ff3602a5f5260205ff35f5260456012600e5ff55f5260205ff3");
+    let init_code = hex!("6008600a5f3960085ff3602a5f5260205ff3");
+    let factory = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(factory, &code)]);
+
+    let mut insp = RecordCreatedAddresses(
+        TracingInspector::new(TracingInspectorConfig::default_parity()),
+        Vec::new(),
+    );
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(factory)).unwrap();
+    assert!(res.result.is_success());
+
+    let predicted = predict_create2_address(factory, U256::from(0x45), keccak256(init_code));
+    assert_eq!(insp.1, vec![(predicted, Some(predicted))]);
+    assert_eq!(insp.0.traces().nodes()[1].trace.address, predicted);
+}
+
+#[test]
+fn test_precompile_inputs() {
+    /*
+    Caller calls modexp with a base of 1 byte, an exponent of 2 bytes and a modulus of 3 bytes,
+    then ecPairing without any pairs:
+
+    PUSH1 0x01 PUSH0 MSTORE PUSH1 0x02 PUSH1 0x20 MSTORE PUSH1 0x03 PUSH1 0x40 MSTORE
+    PUSH1 0x03 PUSH0 PUSH1 0x66 PUSH0 PUSH0 PUSH1 0x05 GAS CALL POP
+    PUSH1 0x20 PUSH0 PUSH0 PUSH0 PUSH0 PUSH1 0x08 GAS CALL POP STOP
+    */
+    let code = hex!("60015f526002602052600360405260035f60665f5f60055af15060205f5f5f5f60085af15000");
+    let caller = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(caller, &code)]);
+
+    let trace = |config: TracingInspectorConfig| {
+        let mut insp = TracingInspector::new(config);
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(caller)).unwrap();
+        assert!(res.result.is_success());
+        insp.traces().nodes().iter().map(|node| node.trace.precompile_input).collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        trace(TracingInspectorConfig::default_geth().precompile_inputs()),
+        vec![
+            None,
+            Some(PrecompileInput::ModExp { base_len: 1, exp_len: 2, mod_len: 3 }),
+            Some(PrecompileInput::EcPairing { pairs: 0 }),
+        ]
+    );
+    assert_eq!(trace(TracingInspectorConfig::default_geth()), vec![None, None, None]);
+}
+
+#[test]
+fn test_into_events() {
+    /*
+    Caller calls the callee:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <callee> GAS CALL POP STOP
+
+    Callee emits a log:
+
+    PUSH0 PUSH0 LOG0 STOP
+    */
+    let caller = address!("0x1000000000000000000000000000000000000001");
+    let callee = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (caller, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (callee, &hex!("5f5fa000")),
+    ]);
+
+    let mut insp =
+        TracingInspector::new(TracingInspectorConfig::default_geth().set_record_logs(true));
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(caller)).unwrap();
+    assert!(res.result.is_success());
+
+    let events = insp.into_traces().into_events();
+    let summary = events
+        .iter()
+        .map(|event| match event {
+            TraceEvent::Enter { idx, trace } => format!("enter {idx} {}", trace.address),
+            TraceEvent::Exit { idx, status, .. } => format!("exit {idx} {status:?}"),
+            TraceEvent::Log { idx, .. } => format!("log {idx}"),
+            TraceEvent::Step { idx, step } => format!("step {idx} {}", step.op),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            format!("enter 0 {caller}"),
+            "step 0 PUSH0".to_string(),
+            "step 0 PUSH0".to_string(),
+            "step 0 PUSH0".to_string(),
+            "step 0 PUSH0".to_string(),
+            "step 0 PUSH0".to_string(),
+            "step 0 PUSH20".to_string(),
+            "step 0 GAS".to_string(),
+            "step 0 CALL".to_string(),
+            format!("enter 1 {callee}"),
+            "step 1 PUSH0".to_string(),
+            "step 1 PUSH0".to_string(),
+            "step 1 LOG0".to_string(),
+            "log 1".to_string(),
+            "step 1 STOP".to_string(),
+            "exit 1 Some(Stop)".to_string(),
+            "step 0 POP".to_string(),
+            "step 0 STOP".to_string(),
+            "exit 0 Some(Stop)".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_into_events_multiple_roots() {
+    /*
+    PUSH0 PUSH0 LOG0 STOP
+    */
+    let code = hex!("5f5fa000");
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[(addr, &code)]);
+    let trace = || {
+        let mut insp =
+            TracingInspector::new(TracingInspectorConfig::default_parity().set_record_logs(true));
+        let mut evm = context.clone().build_mainnet_with_inspector(&mut insp);
+        let res = evm.inspect_tx(call_tx(addr)).unwrap();
+        assert!(res.result.is_success());
+        insp.into_traces()
+    };
+
+    let mut arena = trace();
+    arena.append(trace());
+
+    let summary = arena
+        .into_events()
+        .iter()
+        .map(|event| match event {
+            TraceEvent::Enter { idx, .. } => format!("enter {idx}"),
+            TraceEvent::Exit { idx, .. } => format!("exit {idx}"),
+            TraceEvent::Log { idx, log } => format!("log {idx} {}", log.index),
+            TraceEvent::Step { idx, .. } => format!("step {idx}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(summary, ["enter 0", "log 0 0", "exit 0", "enter 1", "log 1 1", "exit 1"]);
+}
@@ -1,20 +1,53 @@
 use alloy_primitives::{Address, Bytes};
 use colorchoice::ColorChoice;
 use revm::{
+    bytecode::Bytecode,
     context::{BlockEnv, CfgEnv, Evm, TxEnv},
     context_interface::{
         result::{ExecutionResult, HaltReason},
         TransactTo,
     },
+    database::CacheDB,
+    database_interface::EmptyDB,
     handler::{instructions::EthInstructions, EthFrame, EthPrecompiles, EvmTr},
     interpreter::interpreter::EthInterpreter,
     primitives::hardfork::SpecId,
-    Context, Database, DatabaseCommit, ExecuteCommitEvm, InspectCommitEvm, Inspector, Journal,
+    state::AccountInfo,
+    Context, Database, DatabaseCommit, ExecuteCommitEvm, InspectCommitEvm, InspectEvm, Inspector,
+    Journal, MainBuilder, MainContext,
 };
 use revm_inspectors::tracing::{TraceWriter, TraceWriterConfig, TracingInspector};
 
 pub type ContextDb<DB> = Context<BlockEnv, TxEnv, CfgEnv, DB, Journal<DB>, ()>;
 
+/// Returns a Cancun context whose database holds the given contracts.
+pub fn contract_context(contracts: &[(Address, &[u8])]) -> ContextDb<CacheDB<EmptyDB>> {
+    Context::mainnet()
+        .with_db(CacheDB::<EmptyDB>::default())
+        .modify_db_chained(|db| {
+            for (address, code) in contracts {
+                db.insert_account_info(
+                    *address,
+                    AccountInfo {
+                        code: Some(Bytecode::new_raw(Bytes::copy_from_slice(code))),
+                        ..Default::default()
+                    },
+                );
+            }
+        })
+        .modify_cfg_chained(|cfg| cfg.spec = SpecId::CANCUN)
+}
+
+/// Returns a transaction from the zero address calling the given contract.
+pub fn call_tx(to: Address) -> TxEnv {
+    TxEnv {
+        caller: Address::ZERO,
+        gas_limit: 1000000,
+        kind: TransactTo::Call(to),
+        ..Default::default()
+    }
+}
+
 pub fn write_traces(tracer: &TracingInspector) -> String {
     write_traces_with(tracer, TraceWriterConfig::new().color_choice(ColorChoice::Never))
 }
@@ -84,3 +117,20 @@ pub fn inspect_deploy_contract<DB: Database + DatabaseCommit, INSP: Inspector<Co
     });
     output
 }
+
+/// Executes the transaction twice on the given database, each time with a fresh inspector, and
+/// returns whether both runs recorded identical traces.
+pub fn verify_deterministic<DB: Database + Clone>(
+    db: DB,
+    tx: TxEnv,
+    inspector_factory: impl Fn() -> TracingInspector,
+) -> bool {
+    let trace = || {
+        let mut insp = inspector_factory();
+        let mut evm =
+            Context::mainnet().with_db(db.clone()).build_mainnet_with_inspector(&mut insp);
+        evm.inspect_tx(tx.clone()).expect("Expect to be executed");
+        insp.into_traces()
+    };
+    trace() == trace()
+}
@@ -1,15 +1,15 @@
-use crate::utils::{inspect_deploy_contract, write_traces_with};
-use alloy_primitives::{address, b256, bytes, hex, Address, B256, U256};
+use crate::utils::{call_tx, contract_context, inspect_deploy_contract, write_traces_with};
+use alloy_primitives::{address, b256, bytes, hex, keccak256, Address, B256, U256};
 use alloy_sol_types::{sol, SolCall};
 use colorchoice::ColorChoice;
 use revm::{
-    context::TxEnv, context_interface::TransactTo, database::CacheDB, database_interface::EmptyDB,
-    inspector::InspectorEvmTr, primitives::hardfork::SpecId, Context, InspectCommitEvm, InspectEvm,
-    MainBuilder, MainContext,
+    bytecode::Bytecode, context::TxEnv, context_interface::TransactTo, database::CacheDB,
+    database_interface::EmptyDB, inspector::InspectorEvmTr, primitives::hardfork::SpecId,
+    state::AccountInfo, Context, InspectCommitEvm, InspectEvm, MainBuilder, MainContext,
 };
 use revm_inspectors::tracing::{
     types::{DecodedCallData, DecodedInternalCall, DecodedTraceStep},
-    TraceWriterConfig, TracingInspector, TracingInspectorConfig,
+    TraceWriter, TraceWriterConfig, TracingInspector, TracingInspectorConfig,
 };
 use snapbox::{assert_data_eq, data::DataFormat};
 use std::path::Path;
@@ -286,3 +286,198 @@ fn assert_traces(
         }
     }
 }
+
+#[test]
+fn bubble_revert_reasons() {
+    /*
+    A calls B, which calls C, and both forward the revert of their subcall:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <next> GAS CALL POP
+    RETURNDATASIZE PUSH0 PUSH0 RETURNDATACOPY RETURNDATASIZE PUSH0 REVERT
+
+    C reverts with `Error("fail")`:
+
+    PUSH32 0x08c379a0 PUSH0 MSTORE PUSH1 0x20 PUSH1 0x04 MSTORE PUSH1 0x04 PUSH1 0x24 MSTORE
+    PUSH32 "fail" PUSH1 0x44 MSTORE PUSH1 0x64 PUSH0 REVERT
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let c = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[(a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1503d5f5f3e3d5ffd")),
+                (b, &hex!("5f5f5f5f5f7330000000000000000000000000000000000000035af1503d5f5f3e3d5ffd")),
+                (c, &hex!("7f08c379a0000000000000000000000000000000000000000000000000000000005f52602060045260046024527f6661696c0000000000000000000000000000000000000000000000000000000060445260645ffd"))]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(!res.result.is_success());
+
+    let config = TraceWriterConfig::new().color_choice(ColorChoice::Never);
+    let s = write_traces_with(&insp, config.clone());
+    assert!(!s.contains("revert: \"fail\""));
+
+    let s = write_traces_with(&insp, config.bubble_revert_reasons(true));
+    let footers = s.lines().filter(|line| line.contains("[Revert]")).collect::<Vec<_>>();
+    assert_eq!(footers.len(), 3);
+    // innermost call first, the reason of the origin is not repeated
+    assert!(!footers[0].contains("revert: \"fail\""));
+    let origin = format!("← revert: \"fail\" (from {c})");
+    assert!(footers[1].ends_with(&origin), "{}", footers[1]);
+    assert!(footers[2].ends_with(&origin), "{}", footers[2]);
+}
+
+#[test]
+fn truncate_long_calldata() {
+    let addr = address!("0x1000000000000000000000000000000000000001");
+
+    let context = contract_context(&[]).modify_db_chained(|db| {
+        db.insert_account_info(
+            addr,
+            AccountInfo { code: Some(Bytecode::new_raw(bytes!("00"))), ..Default::default() },
+        );
+    });
+
+    let mut data = hex!("12345678").to_vec();
+    data.extend([0xab; 196]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(TxEnv { data: data.into(), ..call_tx(addr) }).unwrap();
+    assert!(res.result.is_success());
+
+    let config = TraceWriterConfig::new().color_choice(ColorChoice::Never);
+    let s = write_traces_with(&insp, config.clone());
+    assert!(s.contains(&format!("::12345678({})", "ab".repeat(196))), "{s}");
+
+    let s = write_traces_with(&insp, config.max_bytes(Some(32)));
+    assert!(s.contains(&format!("::12345678({}…(+164 bytes))", "ab".repeat(32))), "{s}");
+}
+
+#[test]
+fn filter_reverted_frames() {
+    /*
+    A calls B and C:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <c> GAS CALL POP STOP
+
+    B succeeds:
+
+    STOP
+
+    C reverts:
+
+    PUSH0 PUSH0 REVERT
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let c = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[(a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af1505f5f5f5f5f7330000000000000000000000000000000000000035af15000")),
+                (b, &hex!("00")),
+                (c, &hex!("5f5ffd"))]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let mut w = TraceWriter::new(Vec::<u8>::new())
+        .use_colors(ColorChoice::Never)
+        .with_frame_filter(|node| !node.trace.success);
+    w.write_arena(insp.traces()).unwrap();
+    let s = String::from_utf8(w.into_writer()).unwrap();
+
+    // the successful top-level call is kept as the parent of the reverted call
+    let lines = s.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 5, "{s}");
+    assert!(lines[0].contains(&a.to_string()), "{s}");
+    assert_eq!(lines[1], "    ├─ …");
+    assert!(lines[2].contains(&c.to_string()), "{s}");
+    assert!(lines[3].ends_with("← [Revert]"), "{s}");
+    assert!(lines[4].ends_with("← [Stop]"), "{s}");
+    assert!(!s.contains(&b.to_string()), "{s}");
+}
+
+#[test]
+fn call_kind_labels() {
+    /*
+    A delegatecalls B, staticcalls C and creates two empty contracts with CREATE2 and CREATE:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS DELEGATECALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <c> GAS STATICCALL POP
+    PUSH0 PUSH0 PUSH0 PUSH0 CREATE2 POP PUSH0 PUSH0 PUSH0 CREATE POP STOP
+
+    B and C stop immediately:
+
+    STOP
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+    let c = address!("0x3000000000000000000000000000000000000003");
+
+    let context = contract_context(&[(a, &hex!("5f5f5f5f7320000000000000000000000000000000000000025af4505f5f5f5f7330000000000000000000000000000000000000035afa505f5f5f5ff5505f5f5ff05000")),
+                (b, &hex!("00")),
+                (c, &hex!("00"))]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(call_tx(a)).unwrap();
+    assert!(res.result.is_success());
+
+    let s = write_traces_with(&insp, TraceWriterConfig::new().color_choice(ColorChoice::Never));
+    let headers = s.lines().filter(|line| !line.contains('←')).collect::<Vec<_>>();
+    assert_eq!(headers.len(), 5, "{s}");
+    assert!(headers[0].ends_with(&format!("{a}::fallback()")), "{s}");
+    assert!(headers[1].ends_with(&format!("{b}::fallback() [delegatecall]")), "{s}");
+    assert!(headers[2].ends_with(&format!("{c}::fallback() [staticcall]")), "{s}");
+    let create2 = a.create2(B256::ZERO, keccak256([]));
+    assert!(headers[3].ends_with(&format!("new <unknown>@{create2} [create2]")), "{s}");
+    assert!(headers[4].ends_with(&format!("new <unknown>@{}", a.create(1))), "{s}");
+}
+
+#[test]
+fn write_jsonl() {
+    /*
+    A calls B, which reverts:
+
+    PUSH0 PUSH0 PUSH0 PUSH0 PUSH0 PUSH20 <b> GAS CALL POP STOP
+
+    B reverts:
+
+    PUSH0 PUSH0 REVERT
+    */
+    let a = address!("0x1000000000000000000000000000000000000001");
+    let b = address!("0x2000000000000000000000000000000000000002");
+
+    let context = contract_context(&[
+        (a, &hex!("5f5f5f5f5f7320000000000000000000000000000000000000025af15000")),
+        (b, &hex!("5f5ffd")),
+    ]);
+
+    let mut insp = TracingInspector::new(TracingInspectorConfig::default_parity());
+    let mut evm = context.build_mainnet_with_inspector(&mut insp);
+    let res = evm.inspect_tx(TxEnv { data: bytes!("12345678"), ..call_tx(a) }).unwrap();
+    assert!(res.result.is_success());
+
+    let mut w = TraceWriter::new(Vec::<u8>::new());
+    w.write_arena_jsonl(insp.traces()).unwrap();
+    let s = String::from_utf8(w.into_writer()).unwrap();
+
+    let lines = s
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2, "{s}");
+    assert_eq!(lines[0]["depth"], 0);
+    assert_eq!(lines[0]["from"], Address::ZERO.to_string());
+    assert_eq!(lines[0]["to"], a.to_string());
+    assert_eq!(lines[0]["gasUsed"], insp.traces().nodes()[0].trace.gas_used);
+    assert_eq!(lines[0]["input"], "0x12345678");
+    assert!(lines[0]["error"].is_null());
+    assert_eq!(lines[1]["depth"], 1);
+    assert_eq!(lines[1]["to"], b.to_string());
+    assert!(lines[1]["input"].is_null());
+    assert_eq!(lines[1]["error"], "execution reverted");
+}